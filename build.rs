@@ -0,0 +1,88 @@
+#[path = "src/discover.rs"]
+mod discover;
+
+use discover::{DOCS_DIR, DiscoveredPage, discover_pages};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const NOT_FOUND_SLUG: &str = "404";
+
+fn main() -> io::Result<()> {
+    let manifest_dir =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo"));
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/discover.rs");
+    println!("cargo:rerun-if-changed={DOCS_DIR}");
+    // New commits change the last-modified dates without touching `docs/`.
+    let reflog = manifest_dir.join(".git/logs/HEAD");
+    if reflog.exists() {
+        println!("cargo:rerun-if-changed={}", reflog.display());
+    }
+
+    let docs_root = manifest_dir.join(DOCS_DIR);
+    let mut pages = discover_pages(&docs_root)?;
+    // Requests are lowercased before lookup, so a page with capitals could never be served.
+    for page in &pages {
+        if page.slug != page.slug.to_lowercase() {
+            panic!(
+                "{} gives the slug `{}`; rename it to lowercase",
+                page.path, page.slug
+            );
+        }
+    }
+
+    let Some(not_found_index) = pages.iter().position(|page| page.slug == NOT_FOUND_SLUG) else {
+        panic!("{DOCS_DIR}/{NOT_FOUND_SLUG}.md is required for the not-found page");
+    };
+    let not_found = pages.remove(not_found_index);
+
+    let out_path =
+        PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo")).join("pages.rs");
+    let mut out = fs::File::create(out_path)?;
+
+    writeln!(out, "static PAGES: [DocPage; {}] = [", pages.len())?;
+    for page in &pages {
+        writeln!(
+            out,
+            "    {},",
+            page_literal(page, &manifest_dir, &docs_root)
+        )?;
+    }
+    writeln!(out, "];")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "static NOT_FOUND_PAGE: DocPage = {};",
+        page_literal(&not_found, &manifest_dir, &docs_root)
+    )?;
+
+    Ok(())
+}
+
+fn page_literal(page: &DiscoveredPage, manifest_dir: &Path, docs_root: &Path) -> String {
+    let absolute = page.file(docs_root);
+    format!(
+        "DocPage {{ slug: {:?}, path: {:?}, source: include_str!({:?}), last_modified: {:?} }}",
+        page.slug,
+        page.path,
+        absolute.to_string_lossy(),
+        git_last_modified(manifest_dir, &page.path),
+    )
+}
+
+/// Committer date of the last commit touching `relative_path`, in RFC 3339. `None` outside a
+/// git checkout (for example when built as a dependency) or for files never committed.
+fn git_last_modified(manifest_dir: &Path, relative_path: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%cI", "--", relative_path])
+        .current_dir(manifest_dir)
+        .output()
+        .ok()?;
+    let date = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !date.is_empty()).then_some(date)
+}
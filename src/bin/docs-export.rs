@@ -0,0 +1,76 @@
+use manatan_documentation::{
+    SourcePage, all_pages,
+    export::{DEFAULT_OUT_DIR, ExportOptions, SEARCH_INDEX_FILE, SITEMAP_FILE, export_site},
+    not_found_page,
+};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+
+const USAGE: &str = "\
+Usage: docs-export [OUT_DIR]
+
+Writes the compiled-in docs as a static site to OUT_DIR (default: target/docs-site).
+Set DOCS_BASE_URL to the site's public origin to also write canonical links and sitemap.xml.
+";
+
+/// Exports the compiled-in pages as a static site; see `export_site` for the layout.
+///
+/// Set `DOCS_BASE_URL` to the site's public origin to get canonical links, absolute
+/// `og:image` URLs and the sitemap. `preview-docs export` does the same from the files on
+/// disk instead.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args_os().skip(1);
+    let out_dir = match (args.next(), args.next()) {
+        (None, _) => PathBuf::from(DEFAULT_OUT_DIR),
+        (Some(arg), None) if !arg.to_string_lossy().starts_with('-') => PathBuf::from(arg),
+        (Some(arg), None) if matches!(arg.to_str(), Some("-h" | "--help")) => {
+            print!("{USAGE}");
+            return Ok(());
+        }
+        (Some(arg), None) => {
+            eprintln!(
+                "error: unknown option `{}`\n\n{USAGE}",
+                arg.to_string_lossy()
+            );
+            process::exit(2);
+        }
+        (Some(_), Some(_)) => {
+            eprintln!("error: expected at most one output directory\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    let base_url = env::var("DOCS_BASE_URL")
+        .ok()
+        .filter(|value| !value.is_empty());
+    let assets_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+
+    let pages: Vec<SourcePage> = all_pages()
+        .iter()
+        .chain([not_found_page()])
+        .map(SourcePage::from)
+        .collect();
+    let summary = export_site(
+        &pages,
+        &ExportOptions {
+            out_dir: &out_dir,
+            assets_dir: &assets_dir,
+            base_url: base_url.as_deref(),
+            template: None,
+        },
+    )?;
+
+    if !summary.sitemap {
+        println!("[export] Set DOCS_BASE_URL to also write {SITEMAP_FILE}");
+    }
+    println!(
+        "[export] Wrote {} pages, {} redirects, 404.html, {SEARCH_INDEX_FILE} and {} assets to {}",
+        summary.pages,
+        summary.redirects,
+        summary.assets,
+        out_dir.display()
+    );
+    Ok(())
+}
@@ -1,44 +1,116 @@
+#[path = "preview-docs/assets.rs"]
+mod assets;
+#[path = "preview-docs/cli.rs"]
+mod cli;
+
+use assets::{RangeRequest, content_type, etag, is_not_modified, parse_range, range_is_current};
 use axum::{
     Router,
     body::Body,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{
-        StatusCode,
-        header::{CACHE_CONTROL, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode, Uri,
+        header::{
+            ACCEPT_RANGES, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+            IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, LOCATION, RANGE,
+        },
+    },
+    response::{
+        Html, IntoResponse, Response,
+        sse::{Event as SseEvent, KeepAlive, Sse},
     },
-    response::{Html, IntoResponse, Response},
     routing::get,
 };
-use manatan_documentation::{nav_sections, parse_doc};
-use pulldown_cmark::{Options, Parser, html};
+use cli::{Command, Config, USAGE, WatchMode};
+use manatan_documentation::{
+    SourcePage, canonical_slug,
+    check::check_pages,
+    export::{ExportOptions, SEARCH_INDEX_FILE, SITEMAP_FILE, export_site},
+    layout::{Chrome, SearchSource, render_document, render_page_parts},
+    normalize_slug, not_found_page, parse_doc_strict,
+    render::{RenderedPage, docs_path_for_slug, render_source, render_suggestions_html},
+    resolve_asset_path,
+    search::{DEFAULT_RESULT_LIMIT, json_string, results_to_json},
+    site::{DocsRoute, Site, is_scratch_file, slug_for_changed_file},
+    sitemap::{SitemapEntry, robots_txt, sitemap_xml},
+};
+use notify::{EventKind, RecursiveMode, Watcher};
 use std::{
+    collections::{BTreeSet, HashMap},
+    convert::Infallible,
     env, fs,
-    net::SocketAddr,
+    io::{self, SeekFrom},
+    net::{Ipv4Addr, SocketAddr},
     path::{Path as FsPath, PathBuf},
-    sync::{
-        Arc,
-        atomic::{AtomicU64, Ordering},
-    },
-    time::{SystemTime, UNIX_EPOCH},
+    process::{self, Stdio},
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+    time::Duration,
 };
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::{broadcast, mpsc},
+};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use tokio_util::io::ReaderStream;
 
-const DEFAULT_BIND_ADDR: &str = "127.0.0.1:48924";
-const HOT_RELOAD_POLL_MS: u64 = 1000;
-const DOCS_REPO_BASE_URL: &str = "https://github.com/KolbyML/Manatan-Documentation";
+const PREVIEW_BRAND: &str = "Manatan Docs Preview";
+/// A burst of file events (an editor saving through a temp file, a `git checkout`) ends once
+/// nothing has changed for this long, and is then sent to browsers as one reload.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Sent instead of page paths when every open page should reload.
+const RELOAD_ALL: &str = "*";
 
 #[derive(Clone)]
 struct AppState {
-    latest_seen_version: Arc<AtomicU64>,
+    /// Space-separated `/docs/...` paths of changed pages, or [`RELOAD_ALL`].
+    changes: broadcast::Sender<String>,
+    /// The configured base URL, or the address the preview listens on. Canonical links and
+    /// the sitemap both use it, since sitemap URLs are absolute.
+    base_url: Arc<str>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Set once at startup, before anything reads the docs.
+fn config() -> &'static Config {
+    CONFIG.get().expect("the config is loaded at startup")
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let bind_addr = env::var("BIND_ADDR").unwrap_or_else(|_| DEFAULT_BIND_ADDR.to_string());
-    let socket_addr: SocketAddr = bind_addr.parse()?;
+    let config = match cli::load(env::args().skip(1)) {
+        Ok(Some(config)) => CONFIG.get_or_init(|| config),
+        Ok(None) => {
+            print!("{USAGE}");
+            return Ok(());
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\nRun `preview-docs --help` for usage.");
+            process::exit(2);
+        }
+    };
+
+    match &config.command {
+        Command::Serve => serve(config).await,
+        Command::Check => run_link_check(),
+        Command::Export(out_dir) => run_export(config, registry().pages(), out_dir),
+    }
+}
 
-    let initial_version = preview_version_value();
+async fn serve(config: &'static Config) -> Result<(), Box<dyn std::error::Error>> {
+    let socket_addr: SocketAddr = config.bind_addr.parse()?;
+
+    tokio::task::spawn_blocking(reload_registry).await?;
+
+    let (changes, _) = broadcast::channel(16);
+    let _watcher = watch_for_changes(config, changes.clone())?;
     let state = AppState {
-        latest_seen_version: Arc::new(AtomicU64::new(initial_version)),
+        changes,
+        base_url: config
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{socket_addr}"))
+            .into(),
     };
 
     let app = Router::new()
@@ -47,707 +119,595 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/docs/", get(docs_index_handler))
         .route("/docs/{*path}", get(docs_page_handler))
         .route("/assets/{*path}", get(assets_handler))
-        .route("/__preview/version", get(preview_version_handler))
+        .route("/search", get(search_handler))
+        .route("/sitemap.xml", get(sitemap_handler))
+        .route("/robots.txt", get(robots_handler))
+        .route("/__preview/events", get(preview_events_handler))
+        .route("/__preview/fragment", get(preview_fragment_handler))
+        .fallback(fallback_handler)
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind(socket_addr).await?;
-    println!("Preview running at http://{socket_addr}/docs");
+    let mut browser_addr = listener.local_addr()?;
+    if browser_addr.ip().is_unspecified() {
+        browser_addr.set_ip(Ipv4Addr::LOCALHOST.into());
+    }
+    let url = format!("http://{browser_addr}/docs");
+    println!("Previewing {} at {url}", config.docs_dir.display());
+    if config.open {
+        open_in_browser(&url);
+    }
     axum::serve(listener, app).await?;
     Ok(())
 }
 
-async fn docs_index_handler() -> Response {
-    render_docs_response("")
+/// Starts the system's URL opener without waiting for the browser.
+fn open_in_browser(url: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        process::Command::new("open")
+    } else {
+        process::Command::new("xdg-open")
+    };
+    let spawned = command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Err(error) = spawned {
+        eprintln!("[preview] Could not open a browser: {error}");
+    }
 }
 
-async fn docs_page_handler(Path(path): Path<String>) -> Response {
-    render_docs_response(&path)
+/// `check`: reports broken links in every page and exits non-zero if there are any.
+fn run_link_check() -> ! {
+    let problems = check_pages(registry().pages(), &config().assets_dir);
+    for problem in &problems {
+        eprintln!("[check] {problem}");
+    }
+
+    if problems.is_empty() {
+        println!("[check] All links resolve");
+        process::exit(0);
+    }
+    eprintln!("[check] {} broken link(s)", problems.len());
+    process::exit(1);
 }
 
-async fn preview_version_handler(State(state): State<AppState>) -> Response {
-    let version = preview_version_value();
-    let previous = state.latest_seen_version.swap(version, Ordering::SeqCst);
-    if previous != version {
-        println!("[preview] Rebuilding UI: docs changed (version {version})");
+/// `export`: like `docs-export`, from the files on disk instead of the compiled-in pages.
+fn run_export(
+    config: &Config,
+    pages: &[SourcePage],
+    out_dir: &FsPath,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let template = fs::read_to_string(&config.template_path).ok();
+    let summary = export_site(
+        pages,
+        &ExportOptions {
+            out_dir,
+            assets_dir: &config.assets_dir,
+            base_url: config.base_url.as_deref(),
+            template: template.as_deref(),
+        },
+    )?;
+
+    if !summary.sitemap {
+        println!("[export] Pass --base-url or set DOCS_BASE_URL to also write {SITEMAP_FILE}");
     }
+    println!(
+        "[export] Wrote {} pages, {} redirects, 404.html, {SEARCH_INDEX_FILE} and {} assets to {}",
+        summary.pages,
+        summary.redirects,
+        summary.assets,
+        out_dir.display()
+    );
+    Ok(())
+}
 
-    Response::builder()
-        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
-        .header(CACHE_CONTROL, "no-store")
-        .body(Body::from(version.to_string()))
-        .unwrap()
+async fn docs_index_handler(State(state): State<AppState>, uri: Uri) -> Response {
+    docs_response(&state, uri.path(), uri.query())
 }
 
-async fn assets_handler(Path(path): Path<String>) -> Response {
-    let asset_path = FsPath::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(&path);
-    
-    match fs::read(&asset_path) {
-        Ok(content) => {
-            let content_type = match asset_path.extension().and_then(|s| s.to_str()) {
-                Some("png") => "image/png",
-                Some("jpg") | Some("jpeg") => "image/jpeg",
-                Some("gif") => "image/gif",
-                Some("svg") => "image/svg+xml",
-                Some("webp") => "image/webp",
-                _ => "application/octet-stream",
-            };
-            
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(CONTENT_TYPE, content_type)
-                .body(Body::from(content))
-                .unwrap()
-        }
-        Err(_) => {
-            Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Body::from("Asset not found"))
-                .unwrap()
-        }
-    }
+async fn docs_page_handler(State(state): State<AppState>, uri: Uri) -> Response {
+    docs_response(&state, uri.path(), uri.query())
 }
 
-fn render_docs_response(slug: &str) -> Response {
-    let normalized_slug = normalize_slug(slug);
+/// Catches spellings the routes above miss, like `//docs//guides` or `/Docs/guides`.
+async fn fallback_handler(State(state): State<AppState>, uri: Uri) -> Response {
+    docs_response(&state, uri.path(), uri.query())
+}
 
-    if let Some(source) = load_source_for_slug(normalized_slug) {
-        return render_docs_page(StatusCode::OK, normalized_slug, &source);
+/// Renders the page when `path`, as sent and still percent-encoded, is already its canonical
+/// URL and redirects to that URL otherwise, so every page is reachable under one address only.
+fn docs_response(state: &AppState, path: &str, query: Option<&str>) -> Response {
+    let site = registry();
+    match site.route(path, query) {
+        DocsRoute::Render(slug) => render_docs_response(&site, &slug, &state.base_url),
+        DocsRoute::Redirect(location) => redirect_permanently(&location),
+        DocsRoute::Outside => StatusCode::NOT_FOUND.into_response(),
     }
+}
 
-    let not_found_source = load_source_for_slug("404").unwrap_or_else(|| {
-        "---\ntitle: Page not found\ndescription: The requested docs page could not be found.\n---\n\n# Page not found\n"
-            .to_string()
-    });
-    render_docs_page(StatusCode::NOT_FOUND, "404", &not_found_source)
+/// A 301 to `location`, or 400 when it cannot be sent as a header.
+fn redirect_permanently(location: &str) -> Response {
+    match HeaderValue::try_from(location) {
+        Ok(location) => (StatusCode::MOVED_PERMANENTLY, [(LOCATION, location)]).into_response(),
+        Err(_) => StatusCode::BAD_REQUEST.into_response(),
+    }
 }
 
-fn render_docs_page(status: StatusCode, page_slug: &str, source: &str) -> Response {
-    let parsed_doc = parse_doc(source);
-    let mut content_html = markdown_to_html(parsed_doc.body);
-    content_html.push_str(&render_fix_prompt_html(page_slug));
-    let sidebar_html = render_sidebar_html(page_slug);
-    let toc_html = render_toc_html(page_slug);
-    let title = if parsed_doc.title.trim().is_empty() {
-        "Manatan Docs".to_string()
-    } else {
-        format!("{} - Manatan Docs", parsed_doc.title.trim())
-    };
+/// `/search?q=`: ranked results as JSON, from the index the registry keeps for the files on
+/// disk.
+async fn search_handler(Query(params): Query<HashMap<String, String>>) -> Response {
+    let query = params.get("q").map(String::as_str).unwrap_or_default();
+    let results = registry()
+        .search_index()
+        .search(query, DEFAULT_RESULT_LIMIT);
 
-    let page_html = HTML_TEMPLATE
-        .replace("{{TITLE}}", &escape_html(&title))
-        .replace("{{SIDEBAR}}", &sidebar_html)
-        .replace("{{TOC}}", &toc_html)
-        .replace("{{CONTENT}}", &content_html)
-        .replace("{{HOT_RELOAD_POLL_MS}}", &HOT_RELOAD_POLL_MS.to_string());
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json; charset=utf-8")
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(results_to_json(query, &results)))
+        .unwrap()
+}
 
-    if status == StatusCode::OK {
-        Html(page_html).into_response()
-    } else {
-        (status, Html(page_html)).into_response()
-    }
+async fn sitemap_handler(State(state): State<AppState>) -> Response {
+    let entries: Vec<SitemapEntry> = registry()
+        .published()
+        .filter_map(|page| {
+            SitemapEntry::from_source(&page.slug, &page.source, page.last_modified.as_deref())
+        })
+        .collect();
+    let xml = sitemap_xml(&entries, |slug| {
+        format!("{}{}", state.base_url, docs_path_for_slug(slug))
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(xml))
+        .unwrap()
 }
 
-fn render_sidebar_html(active_slug: &str) -> String {
-    let sections = nav_sections();
-    let mut html = String::new();
-
-    for section in sections {
-        html.push_str(&format!("<div class=\"section\">{}</div>", escape_html(section.label)));
-
-        for item in section.items {
-            let active_class = if item.slug == active_slug { " active" } else { "" };
-            html.push_str(&format!(
-                "<a class=\"item{}\" href=\"{}\">{}<span>{}</span></a>",
-                active_class,
-                docs_path_for_slug(item.slug),
-                escape_html(item.label),
-                escape_html(item.hint),
-            ));
-        }
-    }
+async fn robots_handler(State(state): State<AppState>) -> Response {
+    let sitemap_url = format!("{}/sitemap.xml", state.base_url);
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(robots_txt(Some(&sitemap_url))))
+        .unwrap()
+}
 
-    html
+/// Server-Sent Events: one `change` event per debounced burst of file changes.
+async fn preview_events_handler(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let events = BroadcastStream::new(state.changes.subscribe()).map(|change| {
+        // A browser that fell behind has missed changes; reloading is always safe.
+        let data = change.unwrap_or_else(|_| RELOAD_ALL.to_string());
+        Ok(SseEvent::default().event("change").data(data))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
 
-fn render_toc_html(active_slug: &str) -> String {
-    let sections = nav_sections();
-    if sections.is_empty() {
-        return "<div class=\"label\">Explore</div><a href=\"/docs\">Docs overview<div class=\"small\">Start here</div></a>".to_string();
-    }
+/// Watches the docs, assets and template directories and broadcasts what changed. The watcher
+/// stops when dropped.
+fn watch_for_changes(
+    config: &'static Config,
+    changes: broadcast::Sender<String>,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let (paths_tx, mut paths_rx) = mpsc::unbounded_channel::<Vec<PathBuf>>();
+    let handler = move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result
+            && !matches!(event.kind, EventKind::Access(_))
+        {
+            let _ = paths_tx.send(event.paths);
+        }
+    };
+    let mut watcher: Box<dyn Watcher + Send> = match config.watch {
+        WatchMode::Native => Box::new(notify::recommended_watcher(handler)?),
+        WatchMode::Poll(interval) => Box::new(notify::PollWatcher::new(
+            handler,
+            notify::Config::default().with_poll_interval(interval),
+        )?),
+    };
 
-    let active_section = sections
-        .iter()
-        .find(|section| section.items.iter().any(|item| item.slug == active_slug))
-        .unwrap_or(&sections[0]);
-
-    let mut html = String::new();
-    html.push_str("<div class=\"label\">In this section</div>");
-
-    for item in active_section.items {
-        html.push_str(&format!(
-            "<a href=\"{}\">{}<div class=\"small\">{}</div></a>",
-            docs_path_for_slug(item.slug),
-            escape_html(item.label),
-            escape_html(item.hint),
-        ));
+    let mut dirs = vec![config.docs_dir.as_path(), config.assets_dir.as_path()];
+    dirs.extend(config.template_path.parent());
+    for dir in dirs {
+        // Directories inside one already watched would report every change twice.
+        let nested = dir != config.docs_dir && dir.starts_with(&config.docs_dir);
+        if dir.is_dir() && !nested {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
     }
 
-    html
-}
+    tokio::spawn(async move {
+        while let Some(first) = paths_rx.recv().await {
+            let mut changed = BTreeSet::new();
+            changed.extend(first.iter().filter_map(|path| changed_page(config, path)));
+            while let Ok(Some(paths)) = tokio::time::timeout(RELOAD_DEBOUNCE, paths_rx.recv()).await
+            {
+                changed.extend(paths.iter().filter_map(|path| changed_page(config, path)));
+            }
+            if changed.is_empty() {
+                continue;
+            }
 
-fn markdown_to_html(markdown: &str) -> String {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_TASKLISTS);
-    options.insert(Options::ENABLE_FOOTNOTES);
-
-    let parser = Parser::new_ext(markdown, options);
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
-    html_output
+            // Frontmatter edits and new or removed pages can change every page's sidebar.
+            let docs_changed = changed.iter().any(|path| path != RELOAD_ALL);
+            // Reading and rendering every page blocks, so keep it off the async workers.
+            let navigation_changed = docs_changed
+                && tokio::task::spawn_blocking(reload_registry)
+                    .await
+                    .unwrap_or(false);
+            if navigation_changed {
+                changed.extend(
+                    registry()
+                        .pages()
+                        .iter()
+                        .map(|page| docs_path_for_slug(&page.slug)),
+                );
+            }
+
+            let message = if changed.contains(RELOAD_ALL) {
+                RELOAD_ALL.to_string()
+            } else {
+                changed.into_iter().collect::<Vec<_>>().join(" ")
+            };
+            if navigation_changed {
+                println!("[preview] Navigation changed, updating every page");
+            } else {
+                println!("[preview] Reloading: {message}");
+            }
+            // No receivers just means no browser is open.
+            let _ = changes.send(message);
+        }
+    });
+
+    Ok(watcher)
 }
 
-fn docs_path_for_slug(slug: &str) -> String {
-    let normalized = normalize_slug(slug);
-    if normalized.is_empty() {
-        "/docs".to_string()
-    } else {
-        format!("/docs/{normalized}")
+/// The `/docs/...` path of a changed Markdown file, [`RELOAD_ALL`] for assets and the
+/// template, or `None` for files nothing depends on, like editor swap files.
+fn changed_page(config: &Config, path: &FsPath) -> Option<String> {
+    if let Some(slug) = slug_for_changed_file(&config.docs_dir, path) {
+        return Some(docs_path_for_slug(&slug));
     }
+    let in_template_dir = config
+        .template_path
+        .parent()
+        .is_some_and(|dir| path.starts_with(dir));
+    let in_assets_dir = path.starts_with(&config.assets_dir);
+    ((in_assets_dir || in_template_dir) && !is_scratch_file(path)).then(|| RELOAD_ALL.to_string())
 }
 
-fn normalize_slug(slug: &str) -> &str {
-    slug.trim().trim_matches('/')
+/// `/assets/...`: files streamed from the assets directory, with byte ranges so short video
+/// clips can seek, and `ETag`/`Last-Modified` so browsers revalidate instead of downloading
+/// unchanged files again.
+async fn assets_handler(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    let response = match resolve_asset_path(&config().assets_dir, &path) {
+        Some(asset_path) => asset_response(&asset_path, &headers).await.ok(),
+        None => None,
+    };
+    response.unwrap_or_else(|| {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("Asset not found"))
+            .unwrap()
+    })
 }
 
-fn render_fix_prompt_html(page_slug: &str) -> String {
-    let Some(markdown_path) = markdown_path_for_slug(page_slug) else {
-        return String::new();
-    };
+async fn asset_response(path: &FsPath, headers: &HeaderMap) -> io::Result<Response> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let metadata = file.metadata().await?;
+    let len = metadata.len();
+    let modified = metadata.modified()?;
+    let etag = etag(len, modified);
+
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let date = |name| header(name).and_then(|value| httpdate::parse_http_date(value).ok());
+    let response = Response::builder()
+        .header(ETAG, &etag)
+        .header(LAST_MODIFIED, httpdate::fmt_http_date(modified))
+        .header(CACHE_CONTROL, "no-cache")
+        .header(ACCEPT_RANGES, "bytes");
+
+    if is_not_modified(
+        header(IF_NONE_MATCH),
+        date(IF_MODIFIED_SINCE),
+        &etag,
+        modified,
+    ) {
+        return Ok(response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap());
+    }
 
-    let edit_url = format!("{DOCS_REPO_BASE_URL}/edit/main/{markdown_path}");
-    let issue_url = format!("{DOCS_REPO_BASE_URL}/issues/new");
+    let range = if range_is_current(header(IF_RANGE), date(IF_RANGE), &etag, modified) {
+        parse_range(header(RANGE), len)
+    } else {
+        RangeRequest::Full
+    };
+    let (response, start, body_len) = match range {
+        RangeRequest::Full => (response.status(StatusCode::OK), 0, len),
+        RangeRequest::Partial { start, end } => (
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, format!("bytes {start}-{end}/{len}")),
+            start,
+            end - start + 1,
+        ),
+        RangeRequest::Unsatisfiable => {
+            return Ok(response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{len}"))
+                .body(Body::empty())
+                .unwrap());
+        }
+    };
 
-    format!(
-        "<section class=\"fix-prompt\"><div class=\"callout tip\"><strong>Found a docs issue?</strong><div>Spotted outdated steps, typos, or missing details? Help improve this page for everyone.</div></div><div class=\"hero-actions\"><a class=\"btn btn-primary\" href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">Edit this page</a><a class=\"btn\" href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">Open an issue</a></div></section>",
-        escape_html(&edit_url),
-        escape_html(&issue_url)
-    )
+    file.seek(SeekFrom::Start(start)).await?;
+    let body = Body::from_stream(ReaderStream::new(file.take(body_len)));
+    Ok(response
+        .header(CONTENT_TYPE, content_type(path))
+        .header(CONTENT_LENGTH, body_len)
+        .body(body)
+        .unwrap())
 }
 
-fn load_source_for_slug(slug: &str) -> Option<String> {
-    let path = path_for_slug(slug)?;
-    fs::read_to_string(path).ok()
+/// What the preview shows at a slug: the page read from disk, or the 404 page listing
+/// similar pages.
+struct PreviewPage {
+    status: StatusCode,
+    /// `404` for missing pages.
+    slug: String,
+    rendered: RenderedPage,
 }
 
-fn path_for_slug(slug: &str) -> Option<PathBuf> {
-    let relative = markdown_path_for_slug(slug)?;
+fn load_preview_page(registry: &Site, slug: &str) -> PreviewPage {
+    let normalized_slug = normalize_slug(slug);
+    let found = registry
+        .resolve(normalized_slug)
+        .and_then(|page_slug| registry.page(page_slug));
+
+    let (status, page_slug, source, suggestions) = match found {
+        Some(page) => (
+            StatusCode::OK,
+            page.slug.as_str(),
+            page.source.as_str(),
+            Vec::new(),
+        ),
+        None => {
+            let not_found_slug = not_found_page().slug;
+            let not_found_source = registry
+                .page(not_found_slug)
+                .map_or(not_found_page().source, |page| page.source.as_str());
+            let suggestions = registry
+                .suggest(normalized_slug)
+                .into_iter()
+                .map(|page| (page.slug.as_str(), page.source.as_str()))
+                .collect();
+            (
+                StatusCode::NOT_FOUND,
+                not_found_slug,
+                not_found_source,
+                suggestions,
+            )
+        }
+    };
 
-    Some(FsPath::new(env!("CARGO_MANIFEST_DIR")).join(relative))
-}
+    report_frontmatter_problems(registry, page_slug, source);
+    let mut rendered = render_source(source);
+    rendered
+        .body_html
+        .push_str(&render_suggestions_html(&suggestions));
+    if rendered.modified_time.is_none() {
+        rendered.modified_time = registry
+            .page(page_slug)
+            .and_then(|page| page.last_modified.clone());
+    }
 
-fn markdown_path_for_slug(slug: &str) -> Option<&'static str> {
-    match normalize_slug(slug) {
-        "" => Some("docs/index.md"),
-        "guides" => Some("docs/guides/index.md"),
-        "guides/getting-started" => Some("docs/guides/getting-started.md"),
-        "guides/ankiconnect-guide" => Some("docs/guides/ankiconnect-guide.md"),
-        "guides/local-manga" => Some("docs/guides/local-manga.md"),
-        "guides/local-anime" => Some("docs/guides/local-anime.md"),
-        "guides/novels" => Some("docs/guides/novels.md"),
-        "guides/kiku-guide" => Some("docs/guides/kiku-guide.md"),
-        "guides/jellyfin-setup" => Some("docs/guides/jellyfin-setup.md"),
-        "guides/troubleshooting" => Some("docs/guides/troubleshooting.md"),
-        "faq" => Some("docs/faq/index.md"),
-        "faq/general" => Some("docs/faq/general.md"),
-        "faq/local-files" => Some("docs/faq/local-files.md"),
-        "404" => Some("docs/404.md"),
-        _ => None,
+    PreviewPage {
+        status,
+        slug: page_slug.to_string(),
+        rendered,
     }
 }
 
-fn preview_version_value() -> u64 {
-    latest_docs_timestamp(FsPath::new(env!("CARGO_MANIFEST_DIR")).join("docs"))
+fn render_docs_response(site: &Site, slug: &str, base_url: &str) -> Response {
+    let page = load_preview_page(site, slug);
+    let page_url = format!("{base_url}{}", docs_path_for_slug(&page.slug));
+    let live_reload_script =
+        LIVE_RELOAD_SCRIPT.replace("{{PAGE_PATH}}", &json_string(&docs_path_for_slug(slug)));
+    let template = fs::read_to_string(&config().template_path);
+    let chrome = Chrome {
+        brand: Some(PREVIEW_BRAND),
+        banner_html: "<div class=\"preview-note\">Local preview with live reload</div>",
+        body_end_html: &live_reload_script,
+        search: Some(SearchSource::Endpoint("/search")),
+        base_url: Some(base_url),
+        page_url: Some(&page_url),
+        template: template.as_deref().ok(),
+    };
+    let page_html = render_document(
+        &page.slug,
+        markdown_path_for_slug(site, &page.slug).as_deref(),
+        &page.rendered,
+        site.sections(),
+        &chrome,
+    );
+
+    (page.status, Html(page_html)).into_response()
 }
 
-fn latest_docs_timestamp(path: PathBuf) -> u64 {
-    latest_timestamp_recursive(&path).unwrap_or(0)
+/// `/__preview/fragment?path=/docs/...`: the title, sidebar, content and TOC of the page at
+/// `path`, for the live reload script to swap into an open tab.
+async fn preview_fragment_handler(Query(params): Query<HashMap<String, String>>) -> Response {
+    let path = params.get("path").map(String::as_str).unwrap_or_default();
+    let slug_path = path.strip_prefix("/docs").unwrap_or(path);
+    let site = registry();
+    let page = load_preview_page(&site, &canonical_slug(slug_path));
+    let parts = render_page_parts(
+        &page.slug,
+        markdown_path_for_slug(&site, &page.slug).as_deref(),
+        &page.rendered,
+        site.sections(),
+        PREVIEW_BRAND,
+    );
+
+    let fragment = parts.to_fragment_html();
+    Response::builder()
+        .status(page.status)
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(fragment))
+        .unwrap()
 }
 
-fn latest_timestamp_recursive(path: &FsPath) -> Option<u64> {
-    let metadata = fs::metadata(path).ok()?;
-    let mut latest = system_time_to_millis(metadata.modified().unwrap_or(UNIX_EPOCH));
+fn report_frontmatter_problems(site: &Site, page_slug: &str, source: &str) {
+    let (Ok((_, diagnostics)) | Err(diagnostics)) = parse_doc_strict(source);
 
-    if metadata.is_dir() {
-        let entries = fs::read_dir(path).ok()?;
-        for entry in entries.filter_map(Result::ok) {
-            if let Some(child_latest) = latest_timestamp_recursive(&entry.path()) {
-                latest = latest.max(child_latest);
-            }
-        }
+    let path = markdown_path_for_slug(site, page_slug).unwrap_or_else(|| page_slug.to_string());
+    for diagnostic in diagnostics {
+        eprintln!("[preview] {path}:{diagnostic}");
     }
+}
 
-    Some(latest)
+fn markdown_path_for_slug(site: &Site, slug: &str) -> Option<String> {
+    site.page(normalize_slug(slug))
+        .map(|page| page.path.clone())
 }
 
-fn system_time_to_millis(time: SystemTime) -> u64 {
-    time.duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_millis() as u64)
-        .unwrap_or(0)
+/// The docs as they are on disk now. The file watcher reloads it.
+static REGISTRY: RwLock<Option<Arc<Site>>> = RwLock::new(None);
+
+fn registry() -> Arc<Site> {
+    let loaded = REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    loaded.unwrap_or_else(|| {
+        reload_registry();
+        registry()
+    })
 }
 
-fn escape_html(value: &str) -> String {
-    value
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
+/// Rereads the docs directory. Returns whether the sidebar or the aliases changed.
+fn reload_registry() -> bool {
+    let docs_dir = &config().docs_dir;
+    let site = Site::load(docs_dir).unwrap_or_else(|error| {
+        eprintln!("[preview] Could not read {}: {error}", docs_dir.display());
+        Site::new(Vec::new())
+    });
+
+    let mut current = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+    let changed = current
+        .as_ref()
+        .is_none_or(|old| old.navigation_differs(&site));
+    *current = Some(Arc::new(site));
+    changed
 }
 
-const HTML_TEMPLATE: &str = r#"<!doctype html>
-<html lang="en">
-  <head>
-    <meta charset="utf-8" />
-    <meta name="viewport" content="width=device-width, initial-scale=1" />
-    <meta name="color-scheme" content="dark" />
-    <title>{{TITLE}} - Preview</title>
-    <link rel="preconnect" href="https://fonts.googleapis.com" />
-    <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin />
-    <link
-      href="https://fonts.googleapis.com/css2?family=Space+Grotesk:wght@500;600;700&family=Inter:wght@400;500;600&display=swap"
-      rel="stylesheet"
-    />
-    <style>
-      :root {
-        --bg: #0b0e14;
-        --panel: rgba(255, 255, 255, 0.03);
-        --border: rgba(255, 255, 255, 0.08);
-        --text: #f0f4f8;
-        --text-muted: #94a3b8;
-        --link: #7dd3fc;
-        --link-hover: #a7f3d0;
-        --link-underline: rgba(125, 211, 252, 0.38);
-        --link-underline-hover: rgba(167, 243, 208, 0.55);
-        --radius: 18px;
-        --shadow: 0 24px 60px -12px rgba(0, 0, 0, 0.5);
-        --nav-height: 72px;
-      }
-
-      * { box-sizing: border-box; }
-      html { scroll-behavior: smooth; }
-
-      body {
-        margin: 0;
-        background: var(--bg);
-        color: var(--text);
-        font-family: "Inter", sans-serif;
-        line-height: 1.7;
-        overflow-x: hidden;
-        -webkit-font-smoothing: antialiased;
-      }
-
-      body::before {
-        content: "";
-        position: fixed;
-        top: -20%;
-        left: -10%;
-        width: 60%;
-        height: 60%;
-        background: radial-gradient(circle, rgba(46, 204, 113, 0.09), transparent 70%);
-        pointer-events: none;
-        z-index: -1;
-      }
-
-      body::after {
-        content: "";
-        position: fixed;
-        bottom: -25%;
-        right: -15%;
-        width: 70%;
-        height: 70%;
-        background: radial-gradient(circle, rgba(52, 152, 219, 0.09), transparent 70%);
-        pointer-events: none;
-        z-index: -1;
-      }
-
-      a { color: inherit; text-decoration: none; }
-      a:hover { text-decoration: underline; text-underline-offset: 3px; }
-      h1, h2, h3 {
-        font-family: "Space Grotesk", sans-serif;
-        letter-spacing: -0.02em;
-        margin: 0;
-      }
-      p { margin: 0; }
-
-      .container { width: min(1200px, calc(100% - 40px)); margin: 0 auto; }
-
-      .nav {
-        position: sticky;
-        top: 0;
-        z-index: 100;
-        height: var(--nav-height);
-        display: flex;
-        align-items: center;
-        background: rgba(11, 14, 20, 0.72);
-        backdrop-filter: blur(14px);
-        -webkit-backdrop-filter: blur(14px);
-        border-bottom: 1px solid var(--border);
-      }
-
-      .nav-inner {
-        display: flex;
-        align-items: center;
-        justify-content: space-between;
-        gap: 18px;
-      }
-
-      .brand {
-        display: inline-flex;
-        align-items: center;
-        gap: 12px;
-        font-weight: 700;
-        font-size: 18px;
-      }
-
-      .logo-dot {
-        width: 30px;
-        height: 30px;
-        border-radius: 999px;
-        background: radial-gradient(circle at 30% 30%, #6ae7a6, #2ecc71 42%, #3498db 100%);
-        box-shadow: 0 14px 34px -18px rgba(46, 204, 113, 0.55);
-      }
-
-      .nav-links {
-        display: flex;
-        align-items: center;
-        gap: 18px;
-        font-size: 14px;
-        font-weight: 600;
-        color: var(--text-muted);
-      }
-
-      .nav-links a:hover { color: var(--text); text-decoration: none; }
-
-      .pill {
-        display: inline-flex;
-        align-items: center;
-        padding: 8px 14px;
-        border-radius: 999px;
-        border: 1px solid var(--border);
-        background: rgba(255, 255, 255, 0.04);
-        font-size: 13px;
-        color: var(--text);
-        white-space: nowrap;
-      }
-
-      .pill:hover { background: rgba(255, 255, 255, 0.07); text-decoration: none; }
-
-      .page { padding: 32px 0 84px; }
-
-      .preview-note {
-        margin-bottom: 14px;
-        display: inline-flex;
-        gap: 10px;
-        align-items: center;
-        padding: 6px 12px;
-        border-radius: 999px;
-        border: 1px solid rgba(125, 211, 252, 0.25);
-        background: rgba(125, 211, 252, 0.1);
-        color: #7dd3fc;
-        font-size: 13px;
-        font-weight: 600;
-      }
-
-      .doc-grid {
-        margin-top: 10px;
-        display: grid;
-        grid-template-columns: 260px minmax(0, 1fr) 220px;
-        gap: 26px;
-        align-items: start;
-      }
-
-      .card {
-        background: var(--panel);
-        border: 1px solid var(--border);
-        border-radius: var(--radius);
-        box-shadow: var(--shadow);
-      }
-
-      .sidebar {
-        padding: 18px;
-        position: sticky;
-        top: calc(var(--nav-height) + 18px);
-      }
-
-      .section {
-        font-size: 12px;
-        letter-spacing: 0.08em;
-        text-transform: uppercase;
-        color: rgba(148, 163, 184, 0.85);
-        margin-bottom: 12px;
-      }
-
-      .item {
-        display: block;
-        padding: 10px 12px;
-        border-radius: 12px;
-        color: var(--text);
-        border: 1px solid transparent;
-      }
-
-      .item:hover {
-        background: rgba(255, 255, 255, 0.04);
-        border-color: rgba(255, 255, 255, 0.06);
-        text-decoration: none;
-      }
-
-      .item span {
-        display: block;
-        color: rgba(148, 163, 184, 0.92);
-        font-size: 12px;
-        margin-top: 2px;
-        font-weight: 500;
-      }
-
-      .item.active {
-        background: rgba(255, 255, 255, 0.06);
-        border-color: rgba(255, 255, 255, 0.12);
-      }
-
-      .content { padding: 26px; }
-      .content > * + * { margin-top: 14px; }
-      .content h1 { font-size: 34px; line-height: 1.15; margin-top: 6px; }
-      .content h2 { font-size: 26px; margin-top: 34px; }
-      .content h3 { font-size: 18px; margin-top: 22px; }
-      .content p, .content li { color: rgba(240, 244, 248, 0.9); }
-      .content ul, .content ol { margin: 10px 0 0; padding-left: 20px; }
-      .content li { margin: 8px 0; }
-
-      .content a {
-        color: var(--link);
-        text-decoration: underline;
-        text-decoration-color: var(--link-underline);
-        text-underline-offset: 3px;
-        text-decoration-thickness: 2px;
-      }
-
-      .content a:hover {
-        color: var(--link-hover);
-        text-decoration-color: var(--link-underline-hover);
-      }
-
-      .content a.btn {
-        text-decoration: none;
-      }
-
-      .content a.btn:not(.btn-primary) {
-        color: var(--text);
-      }
-
-      .content a.btn:hover {
-        text-decoration: none;
-      }
-
-      .content blockquote {
-        margin: 12px 0 0;
-        border-left: 3px solid rgba(125, 211, 252, 0.5);
-        padding: 8px 14px;
-        background: rgba(125, 211, 252, 0.06);
-        border-radius: 10px;
-      }
-
-      .content code {
-        font-family: ui-monospace, SFMono-Regular, Menlo, Monaco, Consolas, "Liberation Mono", "Courier New", monospace;
-        font-size: 0.95em;
-        background: rgba(255, 255, 255, 0.06);
-        border: 1px solid rgba(255, 255, 255, 0.09);
-        padding: 2px 8px;
-        border-radius: 10px;
-      }
-
-      .content pre {
-        margin: 10px 0 0;
-        padding: 14px 16px;
-        border-radius: 16px;
-        background: rgba(0, 0, 0, 0.35);
-        border: 1px solid rgba(255, 255, 255, 0.08);
-        overflow: auto;
-      }
-
-      .content pre code { background: transparent; border: none; padding: 0; }
-
-      .content img {
-        max-width: 100%;
-        height: auto;
-        border-radius: 12px;
-        margin: 10px 0;
-      }
-
-      .fix-prompt {
-        margin-top: 26px;
-        border-top: 1px solid rgba(255, 255, 255, 0.08);
-        padding-top: 20px;
-      }
-
-      .callout {
-        margin-top: 12px;
-        border-radius: 16px;
-        padding: 14px 16px;
-        border: 1px solid rgba(255, 255, 255, 0.08);
-        background: rgba(255, 255, 255, 0.03);
-      }
-
-      .callout strong {
-        display: block;
-        margin-bottom: 6px;
-        font-family: "Space Grotesk", sans-serif;
-        letter-spacing: -0.01em;
-      }
-
-      .callout.tip {
-        border-color: rgba(46, 204, 113, 0.22);
-        background: rgba(46, 204, 113, 0.06);
-      }
-
-      .hero-actions {
-        margin-top: 14px;
-        display: flex;
-        flex-wrap: wrap;
-        gap: 12px;
-      }
-
-      .btn {
-        display: inline-flex;
-        align-items: center;
-        justify-content: center;
-        padding: 12px 18px;
-        border-radius: 14px;
-        font-weight: 700;
-        font-size: 14px;
-        border: 1px solid var(--border);
-        background: var(--panel);
-        transition: transform 0.2s, background 0.2s, border-color 0.2s;
-      }
-
-      .btn:hover {
-        transform: translateY(-1px);
-        background: rgba(255, 255, 255, 0.06);
-        border-color: rgba(255, 255, 255, 0.12);
-        text-decoration: none;
-      }
-
-      .btn-primary {
-        border: none;
-        background: linear-gradient(135deg, rgba(255, 255, 255, 0.92), rgba(255, 255, 255, 0.86));
-        color: #0b0e14;
-      }
-
-      .btn-primary:hover {
-        background: #fff;
-      }
-
-      .toc {
-        position: sticky;
-        top: calc(var(--nav-height) + 18px);
-        padding: 18px;
-      }
-
-      .toc .label {
-        font-size: 12px;
-        letter-spacing: 0.08em;
-        text-transform: uppercase;
-        color: rgba(148, 163, 184, 0.85);
-        margin-bottom: 10px;
-      }
-
-      .toc a {
-        display: block;
-        padding: 8px 10px;
-        border-radius: 12px;
-        color: rgba(240, 244, 248, 0.82);
-        border: 1px solid transparent;
-      }
-
-      .toc a:hover {
-        background: rgba(255, 255, 255, 0.04);
-        border-color: rgba(255, 255, 255, 0.06);
-        text-decoration: none;
-      }
-
-      .toc .small {
-        font-size: 13px;
-        color: rgba(148, 163, 184, 0.92);
-        margin-top: 2px;
-      }
-
-      @media (max-width: 1050px) {
-        .doc-grid { grid-template-columns: 260px minmax(0, 1fr); }
-        .toc { display: none; }
-      }
-
-      @media (max-width: 820px) {
-        .nav-links { display: none; }
-        .doc-grid { grid-template-columns: 1fr; }
-        .sidebar { position: static; }
-        .content { padding: 20px; }
-      }
-    </style>
-  </head>
-  <body>
-    <header class="nav">
-      <div class="container nav-inner">
-        <a class="brand" href="/docs">
-          <span class="logo-dot" aria-hidden="true"></span>
-          Manatan Docs Preview
-        </a>
-        <nav class="nav-links" aria-label="Primary">
-          <a href="/docs" style="color: var(--text);">Docs</a>
-          <a href="https://github.com/KolbyML/Manatan-Documentation" target="_blank" rel="noopener noreferrer">Repo</a>
-        </nav>
-        <a class="pill" href="https://github.com/KolbyML/Manatan-Documentation" target="_blank" rel="noopener noreferrer">Edit docs</a>
-      </div>
-    </header>
-
-    <main class="page">
-      <div class="container">
-        <div class="preview-note">Local preview with live reload</div>
-        <section class="doc-grid">
-          <aside class="card sidebar">{{SIDEBAR}}</aside>
-          <article class="card content">{{CONTENT}}</article>
-          <aside class="card toc">{{TOC}}</aside>
-        </section>
-      </div>
-    </main>
-
-    <script>
+/// Patches the tab in place when its page changes, keeping the heading the reader is at in
+/// the same spot. Asset and template changes reload the tab, and so does a preview restart
+/// (the event stream reconnects).
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
       (() => {
-        const intervalMs = Number("{{HOT_RELOAD_POLL_MS}}") || 1000;
-        let previousVersion = null;
-
-        async function checkForChanges() {
-          try {
-            const response = await fetch('/__preview/version', { cache: 'no-store' });
-            const version = (await response.text()).trim();
+        const page = {{PAGE_PATH}};
+        const events = new EventSource('/__preview/events');
+        let connected = false;
+
+        // The last heading above the upper third of the viewport, and where it sits now.
+        function scrollAnchor() {
+          let anchor = null;
+          for (const heading of document.querySelectorAll('main .content [id]')) {
+            const top = heading.getBoundingClientRect().top;
+            if (top > window.innerHeight / 3) break;
+            anchor = { id: heading.id, top };
+          }
+          return anchor;
+        }
 
-            if (previousVersion === null) {
-              previousVersion = version;
-              return;
-            }
+        async function patchPage() {
+          const url = '/__preview/fragment?path=' + encodeURIComponent(window.location.pathname);
+          const response = await fetch(url, { cache: 'no-store' });
+          const fragment = new DOMParser().parseFromString(await response.text(), 'text/html');
+          const anchor = scrollAnchor();
+          const scrollY = window.scrollY;
+
+          for (const part of ['sidebar', 'content', 'toc']) {
+            const current = document.querySelector(`main .${part}`);
+            const next = fragment.querySelector(`.${part}`);
+            if (current && next) current.innerHTML = next.innerHTML;
+          }
+          document.title = fragment.title;
 
-            if (version !== previousVersion) {
-              window.location.reload();
-            }
-          } catch (_) {
-            // ignore transient polling errors
+          const heading = anchor && document.getElementById(anchor.id);
+          if (heading) {
+            window.scrollBy(0, heading.getBoundingClientRect().top - anchor.top);
+          } else {
+            window.scrollTo(0, scrollY);
           }
+          document.dispatchEvent(new Event('docs:content-replaced'));
         }
 
-        setInterval(checkForChanges, intervalMs);
-        checkForChanges();
+        events.addEventListener('open', () => {
+          if (connected) {
+            window.location.reload();
+          }
+          connected = true;
+        });
+
+        events.addEventListener('change', (event) => {
+          const changed = event.data.split(' ');
+          if (changed.includes('*')) {
+            window.location.reload();
+          } else if (changed.includes(page)) {
+            patchPage().catch(() => window.location.reload());
+          }
+        });
       })();
-    </script>
-  </body>
-</html>
-"#;
+    </script>"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirects_only_to_valid_header_values() {
+        let response = redirect_permanently("/docs/a%0Ab");
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()[LOCATION], "/docs/a%0Ab");
+        assert_eq!(
+            redirect_permanently("/docs/a\nb").status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn exports_a_root_without_assets() {
+        let root = env::temp_dir().join(format!("preview-export-{}", process::id()));
+        fs::create_dir_all(root.join("docs/guides")).unwrap();
+        fs::write(root.join("docs/guides/anki.md"), "---\ntitle: Anki\n---\n").unwrap();
+        let out_dir = root.join("out");
+        let args = ["export", "--root", root.to_str().unwrap(), "--base-url", ""];
+
+        let config = cli::load(args.iter().map(|arg| arg.to_string()))
+            .expect("config")
+            .expect("not --help");
+        let site = Site::load(&config.docs_dir).expect("docs dir");
+        let exported =
+            run_export(&config, site.pages(), &out_dir).map_err(|error| error.to_string());
+        let wrote_page = out_dir.join("guides/anki/index.html").is_file();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(exported, Ok(()));
+        assert!(wrote_page);
+    }
+}
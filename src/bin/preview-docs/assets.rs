@@ -0,0 +1,259 @@
+//! The request-independent parts of serving `/assets/...` files: content types, byte ranges
+//! and cache validators. Mapping URLs to files is `resolve_asset_path` in the library, which
+//! `check` shares.
+
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The `Content-Type` for a file, by extension. Text types are sent as UTF-8.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        // Text and code
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "xml" => "application/xml",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "vtt" => "text/vtt; charset=utf-8",
+        "srt" => "application/x-subrip",
+        "ass" | "ssa" => "text/x-ssa; charset=utf-8",
+        "wasm" => "application/wasm",
+        // Images
+        "png" => "image/png",
+        "apng" => "image/apng",
+        "jpg" | "jpeg" | "jfif" | "pjpeg" | "pjp" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" | "svgz" => "image/svg+xml",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jxl" => "image/jxl",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "bmp" => "image/bmp",
+        "ico" | "cur" => "image/x-icon",
+        "tif" | "tiff" => "image/tiff",
+        // Video
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "avi" => "video/x-msvideo",
+        "mpeg" | "mpg" => "video/mpeg",
+        "ts" => "video/mp2t",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        // Audio
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "weba" => "audio/webm",
+        "mid" | "midi" => "audio/midi",
+        // Fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        // Documents and archives
+        "pdf" => "application/pdf",
+        "epub" => "application/epub+zip",
+        "cbz" => "application/vnd.comicbook+zip",
+        "cbr" => "application/vnd.comicbook-rar",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "7z" => "application/x-7z-compressed",
+        "rar" => "application/vnd.rar",
+        "apk" => "application/vnd.android.package-archive",
+        "apkg" | "colpkg" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// What a `Range` header asks of a file `len` bytes long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// No range, or one the server may ignore: send the whole file.
+    Full,
+    /// Bytes `start..=end`, both within the file.
+    Partial { start: u64, end: u64 },
+    /// A range starting past the end of the file, answered with 416.
+    Unsatisfiable,
+}
+
+/// Reads a `Range: bytes=...` header. Only single ranges are honoured; malformed and
+/// multi-part ranges get the whole file, which RFC 9110 allows. Video players only ask for
+/// one range at a time anyway.
+pub fn parse_range(header: Option<&str>, len: u64) -> RangeRequest {
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // `bytes=-500`: the last 500 bytes.
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return RangeRequest::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return RangeRequest::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return RangeRequest::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return RangeRequest::Full,
+        },
+    };
+
+    if len == 0 || start >= len {
+        RangeRequest::Unsatisfiable
+    } else {
+        RangeRequest::Partial { start, end }
+    }
+}
+
+/// A strong entity tag from the file's size and modification time, quoted for the header.
+pub fn etag(len: u64, modified: SystemTime) -> String {
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!(
+        "\"{len:x}-{:x}-{:x}\"",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Whether a GET can be answered with 304 Not Modified. `If-None-Match` wins over
+/// `If-Modified-Since` when both are sent, as RFC 9110 requires.
+pub fn is_not_modified(
+    if_none_match: Option<&str>,
+    if_modified_since: Option<SystemTime>,
+    etag: &str,
+    modified: SystemTime,
+) -> bool {
+    if let Some(if_none_match) = if_none_match {
+        // Weak comparison: `W/"x"` matches `"x"`.
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag);
+    }
+    if_modified_since.is_some_and(|since| whole_seconds(modified) <= whole_seconds(since))
+}
+
+/// Whether a `Range` header still applies given `If-Range`: only when the client's copy is
+/// the current one, otherwise it gets the whole new file.
+pub fn range_is_current(
+    if_range: Option<&str>,
+    if_range_date: Option<SystemTime>,
+    etag: &str,
+    modified: SystemTime,
+) -> bool {
+    match if_range.map(str::trim) {
+        None => true,
+        // Strong comparison, so weak tags never match.
+        Some(tag) if tag.starts_with('"') => tag == etag,
+        Some(_) => if_range_date.is_some_and(|date| whole_seconds(modified) == whole_seconds(date)),
+    }
+}
+
+/// HTTP dates have one-second precision.
+fn whole_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        let range = |header: &str| parse_range(Some(header), 1000);
+        let partial = |start, end| RangeRequest::Partial { start, end };
+
+        assert_eq!(parse_range(None, 1000), RangeRequest::Full);
+        assert_eq!(range("bytes=0-499"), partial(0, 499));
+        assert_eq!(range("bytes=500-"), partial(500, 999));
+        assert_eq!(range("bytes=-200"), partial(800, 999));
+        assert_eq!(range("bytes=-5000"), partial(0, 999));
+        assert_eq!(range("bytes=900-5000"), partial(900, 999));
+        assert_eq!(range("bytes=1000-"), RangeRequest::Unsatisfiable);
+        assert_eq!(range("bytes=-0"), RangeRequest::Unsatisfiable);
+        assert_eq!(range("bytes=0-1,5-6"), RangeRequest::Full);
+        assert_eq!(range("bytes=5-1"), RangeRequest::Full);
+        assert_eq!(range("items=0-1"), RangeRequest::Full);
+        assert_eq!(
+            parse_range(Some("bytes=0-"), 0),
+            RangeRequest::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn compares_validators() {
+        let modified = UNIX_EPOCH + Duration::new(1_700_000_000, 500);
+        let tag = etag(1234, modified);
+
+        assert!(is_not_modified(Some(&tag), None, &tag, modified));
+        assert!(is_not_modified(
+            Some(&format!("\"x\", W/{tag}")),
+            None,
+            &tag,
+            modified
+        ));
+        assert!(!is_not_modified(
+            Some("\"x\""),
+            Some(modified),
+            &tag,
+            modified
+        ));
+        assert!(is_not_modified(
+            None,
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            &tag,
+            modified
+        ));
+        assert!(!is_not_modified(None, Some(UNIX_EPOCH), &tag, modified));
+
+        assert!(range_is_current(None, None, &tag, modified));
+        assert!(range_is_current(Some(&tag), None, &tag, modified));
+        assert!(!range_is_current(
+            Some(&format!("W/{tag}")),
+            None,
+            &tag,
+            modified
+        ));
+        assert!(range_is_current(
+            Some("Tue, 14 Nov 2023 22:13:20 GMT"),
+            Some(modified),
+            &tag,
+            modified
+        ));
+    }
+}
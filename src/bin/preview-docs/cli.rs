@@ -0,0 +1,508 @@
+//! Command-line flags and `preview.toml`. Flags win over the config file, which wins over
+//! `BIND_ADDR`, `DOCS_BASE_URL` and the defaults.
+
+use manatan_documentation::{discover::DOCS_DIR, export::DEFAULT_OUT_DIR, parse_toml_document};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEFAULT_BIND_ADDR: &str = "127.0.0.1:48924";
+/// Read from the current directory when `--config` is not given, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "preview.toml";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const TEMPLATE_PATH: &str = "templates/page.html";
+
+pub const USAGE: &str = "\
+Local preview server for the Manatan docs.
+
+Usage: preview-docs [COMMAND] [OPTIONS]
+
+Commands:
+  serve              Serve the docs with live reload (default)
+  check              Report broken links and exit non-zero if there are any
+  export [DIR]       Write the static site to DIR (default: target/docs-site)
+
+Options:
+  --config <FILE>         Read settings from FILE (default: ./preview.toml, if it exists)
+  --bind <ADDR>           Address to listen on (default: $BIND_ADDR or 127.0.0.1:48924)
+  --root <DIR>            Docs checkout to preview, like a PR worktree (default: this crate)
+  --docs <DIR>            Markdown pages (default: ROOT/docs)
+  --assets <DIR>          Files served under /assets/ (default: ROOT/assets)
+  --watch <MODE>          How to notice changes: `native` file events or `poll` (default: native)
+  --poll-interval <MS>    How often `--watch poll` looks for changes (default: 500)
+  --open[=BOOL]           Open the preview in a browser once it is listening
+  --base-url <URL>        Public origin for canonical links, the sitemap and the export
+                          (default: $DOCS_BASE_URL, or the preview's own address)
+  -h, --help              Print this help
+
+`--check` and `--export [DIR]` also work in place of the commands. The page template is read
+from ROOT/templates/page.html, falling back to the compiled-in one.
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Serve,
+    Check,
+    Export(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    /// OS file events, through `notify`'s recommended watcher.
+    Native,
+    /// Rescans the watched directories, for file systems without events like network shares
+    /// or some container mounts.
+    Poll(Duration),
+}
+
+/// Everything the preview needs to know to run, with paths canonicalised so they compare
+/// equal to the paths the file watcher reports.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub command: Command,
+    pub bind_addr: String,
+    pub docs_dir: PathBuf,
+    pub assets_dir: PathBuf,
+    pub template_path: PathBuf,
+    pub watch: WatchMode,
+    pub open: bool,
+    /// Public origin of the site without a trailing `/`, like `https://docs.example.com`.
+    pub base_url: Option<String>,
+}
+
+/// The settings flags and `preview.toml` share. `None` leaves the setting to the next source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Settings {
+    bind: Option<String>,
+    root: Option<PathBuf>,
+    docs: Option<PathBuf>,
+    assets: Option<PathBuf>,
+    watch: Option<WatchKind>,
+    poll_interval: Option<Duration>,
+    open: Option<bool>,
+    base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchKind {
+    Native,
+    Poll,
+}
+
+impl Settings {
+    fn or(self, fallback: Settings) -> Settings {
+        Settings {
+            bind: self.bind.or(fallback.bind),
+            root: self.root.or(fallback.root),
+            docs: self.docs.or(fallback.docs),
+            assets: self.assets.or(fallback.assets),
+            watch: self.watch.or(fallback.watch),
+            poll_interval: self.poll_interval.or(fallback.poll_interval),
+            open: self.open.or(fallback.open),
+            base_url: self.base_url.or(fallback.base_url),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    command: Option<Command>,
+    config: Option<PathBuf>,
+    settings: Settings,
+    help: bool,
+}
+
+/// Reads the flags and the config file. `Ok(None)` means `--help` was asked for.
+pub fn load(args: impl IntoIterator<Item = String>) -> Result<Option<Config>, String> {
+    resolve(
+        parse_args(args)?,
+        Path::new(DEFAULT_CONFIG_FILE),
+        env::var("BIND_ADDR").ok(),
+        env::var("DOCS_BASE_URL").ok(),
+    )
+}
+
+/// [`load`] with the process environment passed in: the config file read when `--config` is
+/// not given, if it exists, `$BIND_ADDR` and `$DOCS_BASE_URL`.
+fn resolve(
+    args: Args,
+    default_config: &Path,
+    env_bind_addr: Option<String>,
+    env_base_url: Option<String>,
+) -> Result<Option<Config>, String> {
+    if args.help {
+        return Ok(None);
+    }
+
+    let file_settings = match &args.config {
+        Some(path) => read_config_file(path)?,
+        None if default_config.is_file() => read_config_file(default_config)?,
+        None => Settings::default(),
+    };
+    let settings = args.settings.or(file_settings);
+
+    let root = settings
+        .root
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    let docs_dir = settings.docs.unwrap_or_else(|| root.join(DOCS_DIR));
+    let assets_dir = settings.assets.unwrap_or_else(|| root.join("assets"));
+    let watch = match settings.watch.unwrap_or(WatchKind::Native) {
+        WatchKind::Native => WatchMode::Native,
+        WatchKind::Poll => WatchMode::Poll(settings.poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL)),
+    };
+
+    Ok(Some(Config {
+        command: args.command.unwrap_or(Command::Serve),
+        bind_addr: settings
+            .bind
+            .or(env_bind_addr)
+            .unwrap_or_else(|| DEFAULT_BIND_ADDR.to_string()),
+        docs_dir: canonical_dir(&docs_dir, "docs")?,
+        // A checkout without assets or a template is still worth previewing.
+        assets_dir: fs::canonicalize(&assets_dir).unwrap_or(assets_dir),
+        template_path: fs::canonicalize(root.join(TEMPLATE_PATH))
+            .unwrap_or_else(|_| root.join(TEMPLATE_PATH)),
+        watch,
+        open: settings.open.unwrap_or(false),
+        base_url: settings
+            .base_url
+            .or(env_base_url)
+            .map(|base| base.trim_end_matches('/').to_string())
+            .filter(|base| !base.is_empty()),
+    }))
+}
+
+fn canonical_dir(path: &Path, what: &str) -> Result<PathBuf, String> {
+    match fs::canonicalize(path) {
+        Ok(path) if path.is_dir() => Ok(path),
+        Ok(_) => Err(format!(
+            "{what} directory {} is not a directory",
+            path.display()
+        )),
+        Err(error) => Err(format!("{what} directory {}: {error}", path.display())),
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().peekable();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = |flag: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("`{flag}` needs a value"))
+        };
+        let no_value = |flag: &str| match &inline_value {
+            Some(value) => Err(format!("`{flag}` does not take a value, got `{value}`")),
+            None => Ok(()),
+        };
+
+        let settings = &mut parsed.settings;
+        match name.as_str() {
+            "-h" | "--help" => {
+                no_value(&name)?;
+                parsed.help = true;
+            }
+            "--config" => parsed.config = Some(PathBuf::from(value(&name)?)),
+            "--bind" => settings.bind = Some(value(&name)?),
+            "--base-url" => settings.base_url = Some(value(&name)?),
+            "--root" => settings.root = Some(PathBuf::from(value(&name)?)),
+            "--docs" => settings.docs = Some(PathBuf::from(value(&name)?)),
+            "--assets" => settings.assets = Some(PathBuf::from(value(&name)?)),
+            "--watch" => settings.watch = Some(parse_watch_kind(&value(&name)?)?),
+            "--poll-interval" => {
+                settings.poll_interval = Some(parse_poll_interval(&value(&name)?)?);
+            }
+            // Only the inline form takes a value, so `--open serve` still means the command.
+            "--open" => {
+                settings.open = Some(match &inline_value {
+                    Some(value) => parse_bool(&name, value)?,
+                    None => true,
+                });
+            }
+            "serve" | "check" | "--check" | "export" | "--export" => {
+                if name == "--check" {
+                    no_value(&name)?;
+                }
+                if parsed.command.is_some() {
+                    return Err(format!("unexpected `{name}`, a command was already given"));
+                }
+                parsed.command = Some(match name.trim_start_matches("--") {
+                    "serve" => Command::Serve,
+                    "check" => Command::Check,
+                    _ => {
+                        let out_dir = match inline_value {
+                            Some(dir) => dir,
+                            None => args
+                                .next_if(|next| !next.starts_with('-'))
+                                .unwrap_or_else(|| DEFAULT_OUT_DIR.to_string()),
+                        };
+                        Command::Export(PathBuf::from(out_dir))
+                    }
+                });
+            }
+            _ if name.starts_with('-') => return Err(format!("unknown option `{name}`")),
+            _ => return Err(format!("unexpected argument `{name}`")),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn parse_bool(flag: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("`{flag}` expects `true` or `false`, got `{value}`")),
+    }
+}
+
+fn parse_watch_kind(mode: &str) -> Result<WatchKind, String> {
+    match mode {
+        "native" => Ok(WatchKind::Native),
+        "poll" => Ok(WatchKind::Poll),
+        _ => Err(format!(
+            "unknown watch mode `{mode}`, expected `native` or `poll`"
+        )),
+    }
+}
+
+fn parse_poll_interval(millis: &str) -> Result<Duration, String> {
+    match millis.parse::<u64>() {
+        Ok(millis) if millis > 0 => Ok(Duration::from_millis(millis)),
+        _ => Err(format!(
+            "poll interval `{millis}` is not a positive number of milliseconds"
+        )),
+    }
+}
+
+/// Reads `preview.toml`. Relative paths in it are relative to the file, not to wherever the
+/// preview was started.
+fn read_config_file(path: &Path) -> Result<Settings, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let (values, diagnostics) = parse_toml_document(&text);
+
+    let mut problems: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| format!("{}:{diagnostic}", path.display()))
+        .collect();
+    let mut settings = Settings::default();
+    for (key, value) in &values {
+        let path_value = || value.as_str().map(|dir| base.join(dir));
+        let result = match key.as_str() {
+            "bind" => set(
+                &mut settings.bind,
+                value.as_str().map(str::to_string),
+                "a string",
+            ),
+            "base_url" => set(
+                &mut settings.base_url,
+                value.as_str().map(str::to_string),
+                "a string",
+            ),
+            "root" => set(&mut settings.root, path_value(), "a path"),
+            "docs" => set(&mut settings.docs, path_value(), "a path"),
+            "assets" => set(&mut settings.assets, path_value(), "a path"),
+            "watch" => set(
+                &mut settings.watch,
+                value.as_str().and_then(|mode| parse_watch_kind(mode).ok()),
+                "`\"native\"` or `\"poll\"`",
+            ),
+            "poll_interval" => set(
+                &mut settings.poll_interval,
+                value
+                    .as_i64()
+                    .and_then(|millis| u64::try_from(millis).ok())
+                    .filter(|millis| *millis > 0)
+                    .map(Duration::from_millis),
+                "a positive number of milliseconds",
+            ),
+            "open" => set(&mut settings.open, value.as_bool(), "`true` or `false`"),
+            _ => Err("is not a preview setting".to_string()),
+        };
+        if let Err(problem) = result {
+            problems.push(format!("{}: `{key}` {problem}", path.display()));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(settings)
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+fn set<T>(field: &mut Option<T>, value: Option<T>, expected: &str) -> Result<(), String> {
+    match value {
+        Some(value) => {
+            *field = Some(value);
+            Ok(())
+        }
+        None => Err(format!("should be {expected}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// Resolves `flags` with `preview.toml` text `config`, `$BIND_ADDR` set to `env` and
+    /// `$DOCS_BASE_URL` set to `https://env.example.com`.
+    fn load_with(flags: &[&str], config: &str, env: Option<&str>) -> Result<Config, String> {
+        let dir = env::temp_dir().join(format!("preview-cli-{}", std::process::id()));
+        fs::create_dir_all(dir.join("docs")).map_err(|error| error.to_string())?;
+        // Tests run in parallel, so each call gets its own file.
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let file = dir.join(format!("{}.toml", CALLS.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&file, config).map_err(|error| error.to_string())?;
+
+        let config = resolve(
+            args(flags)?,
+            &file,
+            env.map(str::to_string),
+            Some("https://env.example.com".to_string()),
+        );
+        let _ = fs::remove_file(&file);
+        config.map(|config| config.expect("not --help"))
+    }
+
+    #[test]
+    fn flags_win_over_the_config_file_and_the_file_over_the_environment() {
+        let file = "bind = \"0.0.0.0:1\"\nopen = true\nwatch = \"poll\"\npoll_interval = 50\n";
+
+        let config = load_with(&[], file, Some("10.0.0.1:3")).expect("config");
+        assert_eq!(config.bind_addr, "0.0.0.0:1");
+        assert!(config.open);
+        assert_eq!(config.watch, WatchMode::Poll(Duration::from_millis(50)));
+
+        let config = load_with(
+            &[
+                "--bind=127.0.0.1:2",
+                "--open=false",
+                "--poll-interval",
+                "75",
+            ],
+            file,
+            Some("10.0.0.1:3"),
+        )
+        .expect("config");
+        assert_eq!(config.bind_addr, "127.0.0.1:2");
+        assert!(!config.open);
+        assert_eq!(config.watch, WatchMode::Poll(Duration::from_millis(75)));
+
+        let config = load_with(&[], "", Some("10.0.0.1:3")).expect("config");
+        assert_eq!(config.bind_addr, "10.0.0.1:3");
+        let config = load_with(&["--open"], "", None).expect("config");
+        assert_eq!(config.bind_addr, DEFAULT_BIND_ADDR);
+        assert!(config.open);
+        assert_eq!(config.watch, WatchMode::Native);
+        assert_eq!(config.command, Command::Serve);
+    }
+
+    #[test]
+    fn takes_the_base_url_from_flags_the_file_or_the_environment() {
+        let file = "base_url = \"https://file.example.com/\"\n";
+        let base_url =
+            |flags: &[&str], file: &str| load_with(flags, file, None).expect("config").base_url;
+
+        assert_eq!(
+            base_url(&["--base-url", "https://flag.example.com"], file).as_deref(),
+            Some("https://flag.example.com")
+        );
+        assert_eq!(
+            base_url(&[], file).as_deref(),
+            Some("https://file.example.com")
+        );
+        assert_eq!(
+            base_url(&[], "").as_deref(),
+            Some("https://env.example.com")
+        );
+    }
+
+    #[test]
+    fn resolves_config_paths_against_the_file() {
+        let config = load_with(&["check"], "docs = \"docs\"\n", None).expect("config");
+        assert!(
+            config
+                .docs_dir
+                .ends_with(format!("preview-cli-{}/docs", std::process::id()))
+        );
+        assert_eq!(config.command, Command::Check);
+    }
+
+    #[test]
+    fn parses_commands_and_inline_values() {
+        assert_eq!(
+            args(&["--export=out", "--watch=poll"])
+                .expect("args")
+                .command,
+            Some(Command::Export(PathBuf::from("out")))
+        );
+        assert_eq!(
+            args(&["export", "--open"]).expect("args").command,
+            Some(Command::Export(PathBuf::from(DEFAULT_OUT_DIR)))
+        );
+        assert!(args(&["--help"]).expect("args").help);
+        assert_eq!(
+            args(&["--open=yes"]).expect("args").settings.open,
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_flags_and_values() {
+        for (flags, error) in [
+            (
+                &["--open=maybe"][..],
+                "`--open` expects `true` or `false`, got `maybe`",
+            ),
+            (
+                &["--check=foo"],
+                "`--check` does not take a value, got `foo`",
+            ),
+            (&["--help=x"], "`--help` does not take a value, got `x`"),
+            (&["--bind"], "`--bind` needs a value"),
+            (
+                &["--watch", "inotify"],
+                "unknown watch mode `inotify`, expected `native` or `poll`",
+            ),
+            (
+                &["--poll-interval=0"],
+                "poll interval `0` is not a positive number of milliseconds",
+            ),
+            (
+                &["check", "serve"],
+                "unexpected `serve`, a command was already given",
+            ),
+            (&["--verbose"], "unknown option `--verbose`"),
+            (&["docs"], "unexpected argument `docs`"),
+        ] {
+            assert_eq!(args(flags).err().as_deref(), Some(error), "{flags:?}");
+        }
+
+        let error = load_with(&[], "open = \"yes\"\nport = 1\n", None).expect_err("bad file");
+        assert!(
+            error.contains("`open` should be `true` or `false`"),
+            "{error}"
+        );
+        assert!(error.contains("`port` is not a preview setting"), "{error}");
+        let error = load_with(&["--docs", "/no/such/dir"], "", None).expect_err("no docs");
+        assert!(error.starts_with("docs directory /no/such/dir:"), "{error}");
+    }
+}
@@ -0,0 +1,371 @@
+use std::{collections::HashMap, fmt, path::Path};
+
+use pulldown_cmark::{BrokenLink, Event, LinkType, Parser, Tag};
+
+use crate::{
+    SourcePage, all_pages, build_aliases, is_valid_alias, normalize_slug, parse_doc,
+    percent_decode,
+    render::{markdown_options, render_source},
+    resolve_asset_path,
+};
+
+/// A link in a docs page that points at nothing, an alias that cannot be one, or a page
+/// whose file name gives it a URL nothing can reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkProblem {
+    /// Markdown file the link appears in, relative to the crate root.
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub kind: LinkProblemKind,
+    /// The link destination, reference label or alias as written.
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkProblemKind {
+    /// `[text][label]` or `![alt][label]` without a `[label]: url` definition.
+    UndefinedReference,
+    /// A `/docs/...` link to a slug `find_page` cannot resolve.
+    UnknownPage,
+    /// A `#fragment` that matches no heading on the target page.
+    UnknownAnchor,
+    /// An `/assets/...` URL the preview would not serve: no such file under the assets
+    /// directory, a path leading out of it, or a dotfile.
+    MissingAsset,
+    /// An `aliases`/`redirect_from` entry with empty, `.` or `..` segments, or one naming a
+    /// file the export writes itself.
+    InvalidAlias,
+    /// A page whose slug has capitals. Requested paths are lowercased, so it is never served.
+    UppercaseSlug,
+}
+
+impl fmt::Display for LinkProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            LinkProblemKind::UndefinedReference => "undefined link reference",
+            LinkProblemKind::UnknownPage => "link to unknown page",
+            LinkProblemKind::UnknownAnchor => "link to unknown heading",
+            LinkProblemKind::MissingAsset => "missing asset",
+            LinkProblemKind::InvalidAlias => "alias is not a plain docs path",
+            LinkProblemKind::UppercaseSlug => "page slug is not lowercase",
+        };
+        write!(
+            f,
+            "{}:{}:{}: {message} `{}`",
+            self.path, self.line, self.column, self.target
+        )
+    }
+}
+
+/// Checks every page in `all_pages()`, resolving `/assets/...` URLs against `assets_dir`.
+pub fn check_links(assets_dir: &Path) -> Vec<LinkProblem> {
+    check_pages(&compiled_pages(), assets_dir)
+}
+
+/// Checks `pages`, resolving `/docs/...` links among them and their aliases rather than the
+/// compiled-in pages. The preview uses this on the files currently on disk.
+pub fn check_pages(pages: &[SourcePage], assets_dir: &Path) -> Vec<LinkProblem> {
+    let mut targets = LinkTargets::new(pages);
+    pages
+        .iter()
+        .flat_map(|page| {
+            let mut problems: Vec<LinkProblem> = (page.slug != page.slug.to_lowercase())
+                .then(|| LinkProblem {
+                    path: page.path.clone(),
+                    line: 1,
+                    column: 1,
+                    kind: LinkProblemKind::UppercaseSlug,
+                    target: page.slug.clone(),
+                })
+                .into_iter()
+                .collect();
+            problems.extend(check_page_with(
+                &page.path,
+                &page.source,
+                assets_dir,
+                &mut targets,
+            ));
+            problems
+        })
+        .collect()
+}
+
+/// Checks a single page's source. `path` is only used to label the problems.
+pub fn check_page(path: &str, source: &str, assets_dir: &Path) -> Vec<LinkProblem> {
+    let pages = compiled_pages();
+    check_page_with(path, source, assets_dir, &mut LinkTargets::new(&pages))
+}
+
+fn compiled_pages() -> Vec<SourcePage> {
+    all_pages().iter().map(SourcePage::from).collect()
+}
+
+/// The pages links can point at, with heading ids per slug so each linked page is only
+/// rendered once per run.
+struct LinkTargets<'a> {
+    pages: &'a [SourcePage],
+    aliases: HashMap<String, String>,
+    ids: HashMap<&'a str, Vec<String>>,
+}
+
+impl<'a> LinkTargets<'a> {
+    fn new(pages: &'a [SourcePage]) -> Self {
+        let aliases = build_aliases(
+            pages
+                .iter()
+                .map(|page| (page.slug.as_str(), page.source.as_str())),
+        );
+        LinkTargets {
+            pages,
+            aliases,
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Like `find_page`: the page at `slug`, or the page listing it as an alias.
+    fn find(&self, slug: &str) -> Option<&'a SourcePage> {
+        let slug = self.aliases.get(slug).map_or(slug, String::as_str);
+        self.pages.iter().find(|page| page.slug == slug)
+    }
+
+    fn contains(&mut self, page: &'a SourcePage, id: &str) -> bool {
+        self.ids
+            .entry(&page.slug)
+            .or_insert_with(|| heading_ids(&render_source(&page.source).headings))
+            .iter()
+            .any(|existing| existing == id)
+    }
+}
+
+fn heading_ids(headings: &[crate::render::Heading]) -> Vec<String> {
+    headings.iter().map(|heading| heading.id.clone()).collect()
+}
+
+fn check_page_with(
+    path: &str,
+    source: &str,
+    assets_dir: &Path,
+    targets: &mut LinkTargets<'_>,
+) -> Vec<LinkProblem> {
+    let parsed = parse_doc(source);
+    let body = parsed.body;
+    let body_offset = body.as_ptr() as usize - source.as_ptr() as usize;
+    let own_ids = heading_ids(&render_source(source).headings);
+
+    let problem = |offset: usize, kind, target: &str| {
+        let (line, column) = line_and_column(source, body_offset + offset);
+        LinkProblem {
+            path: path.to_string(),
+            line,
+            column,
+            kind,
+            target: target.to_string(),
+        }
+    };
+
+    let mut undefined = Vec::new();
+    let mut destinations = Vec::new();
+    {
+        let callback = |link: BrokenLink<'_>| {
+            // A bare `[text]` is usually literal text, so only flag explicit references.
+            if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+                undefined.push((link.span.start, link.reference.to_string()));
+            }
+            None
+        };
+        let parser =
+            Parser::new_with_broken_link_callback(body, markdown_options(), Some(callback));
+        for (event, range) in parser.into_offset_iter() {
+            if let Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) = event {
+                destinations.push((range.start, dest_url.to_string()));
+            }
+        }
+    }
+
+    let mut problems: Vec<LinkProblem> = undefined
+        .iter()
+        .map(|(offset, label)| problem(*offset, LinkProblemKind::UndefinedReference, label))
+        .collect();
+
+    // Frontmatter values are not spanned, so point at where the alias text first appears.
+    for alias in &parsed.frontmatter.aliases {
+        if !is_valid_alias(alias) {
+            let (line, column) = line_and_column(source, source.find(alias.as_str()).unwrap_or(0));
+            problems.push(LinkProblem {
+                path: path.to_string(),
+                line,
+                column,
+                kind: LinkProblemKind::InvalidAlias,
+                target: alias.clone(),
+            });
+        }
+    }
+
+    for (offset, url) in destinations {
+        let kind = if let Some(fragment) = url.strip_prefix('#') {
+            (!own_ids.iter().any(|id| id == fragment)).then_some(LinkProblemKind::UnknownAnchor)
+        } else if let Some((slug, fragment)) = split_docs_link(&url) {
+            match targets.find(slug) {
+                None => Some(LinkProblemKind::UnknownPage),
+                Some(page) => match fragment {
+                    Some(fragment) if !targets.contains(page, fragment) => {
+                        Some(LinkProblemKind::UnknownAnchor)
+                    }
+                    _ => None,
+                },
+            }
+        } else if let Some(asset) = url.strip_prefix("/assets/") {
+            let asset = percent_decode(asset.split(['?', '#']).next().unwrap_or_default());
+            resolve_asset_path(assets_dir, &asset)
+                .is_none()
+                .then_some(LinkProblemKind::MissingAsset)
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            problems.push(problem(offset, kind, &url));
+        }
+    }
+
+    problems.sort_by_key(|problem| (problem.line, problem.column));
+    problems
+}
+
+/// Splits a `/docs/...` URL into its slug and `#fragment`, dropping any query string.
+fn split_docs_link(url: &str) -> Option<(&str, Option<&str>)> {
+    let rest = url.strip_prefix("/docs")?;
+    if !(rest.is_empty() || rest.starts_with(['/', '#', '?'])) {
+        return None;
+    }
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let slug = rest.split('?').next().unwrap_or_default();
+    Some((normalize_slug(slug), fragment))
+}
+
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets_dir() -> &'static Path {
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets"))
+    }
+
+    #[test]
+    fn every_link_in_the_docs_resolves() {
+        let problems = check_links(assets_dir());
+        let report: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert!(problems.is_empty(), "broken links:\n{}", report.join("\n"));
+    }
+
+    #[test]
+    fn reports_each_kind_of_broken_link() {
+        let source = "---\ntitle: Test\ndescription: Links\n---\n\n# Test\n\n\
+            ![Screenshot][IMG99]\n\
+            [Missing page](/docs/guides/nope) and [known](/docs/guides/local-manga)\n\
+            [Bad anchor](/docs/guides/troubleshooting#no-such-heading) [ok](#test) [bad](#nope)\n\
+            ![Image](/assets/jellyfin-setup/missing.png)\n\
+            Plain [brackets] stay text.\n";
+
+        let problems = check_page("docs/test.md", source, assets_dir());
+        let found: Vec<_> = problems
+            .iter()
+            .map(|problem| (problem.line, problem.column, problem.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (8, 1, LinkProblemKind::UndefinedReference),
+                (9, 1, LinkProblemKind::UnknownPage),
+                (10, 1, LinkProblemKind::UnknownAnchor),
+                (10, 72, LinkProblemKind::UnknownAnchor),
+                (11, 1, LinkProblemKind::MissingAsset),
+            ]
+        );
+        assert_eq!(
+            problems[1].to_string(),
+            "docs/test.md:9:1: link to unknown page `/docs/guides/nope`"
+        );
+    }
+
+    #[test]
+    fn reports_aliases_outside_the_docs() {
+        let source = "---\ntitle: Test\naliases:\n  - guides/old-test\n  - ../../outside\n---\n";
+        let problems = check_page("docs/test.md", source, assets_dir());
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["docs/test.md:5:5: alias is not a plain docs path `../../outside`"]
+        );
+    }
+
+    #[test]
+    fn reports_slugs_with_capitals() {
+        let page = |slug: &str| SourcePage {
+            slug: slug.to_string(),
+            path: format!("docs/{slug}.md"),
+            source: "---\ntitle: Test\n---\n\nBody.\n".to_string(),
+            last_modified: None,
+        };
+        let problems = check_pages(&[page("guides/Sync"), page("guides/sync-2")], assets_dir());
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["docs/guides/Sync.md:1:1: page slug is not lowercase `guides/Sync`"]
+        );
+    }
+
+    #[test]
+    fn decodes_escaped_asset_urls() {
+        let dir = std::env::temp_dir().join(format!("check-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        std::fs::write(dir.join("my clip.mp4"), "").expect("asset");
+
+        let source = "---\ntitle: Test\n---\n\n\
+            ![Clip](/assets/my%20clip.mp4)\n\
+            ![Other](/assets/my%20other%20clip.mp4)\n";
+        let problems = check_page("docs/test.md", source, &dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["docs/test.md:6:1: missing asset `/assets/my%20other%20clip.mp4`"]
+        );
+    }
+
+    #[test]
+    fn rejects_asset_urls_the_preview_would_not_serve() {
+        let dir = std::env::temp_dir().join(format!("check-escapes-{}", std::process::id()));
+        let assets = dir.join("assets");
+        std::fs::create_dir_all(&assets).expect("temp dir");
+        std::fs::write(assets.join(".hidden"), "").expect("dotfile");
+        std::fs::write(dir.join("outside.png"), "").expect("outside file");
+
+        let source = "---\ntitle: Test\n---\n\n\
+            ![Up](/assets/../outside.png)\n\
+            ![Encoded](/assets/%2E%2E/outside.png)\n\
+            ![Hidden](/assets/.hidden)\n";
+        let problems = check_page("docs/test.md", source, &assets);
+        let _ = std::fs::remove_dir_all(&dir);
+        let found: Vec<_> = problems
+            .iter()
+            .map(|problem| (problem.line, problem.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (5, LinkProblemKind::MissingAsset),
+                (6, LinkProblemKind::MissingAsset),
+                (7, LinkProblemKind::MissingAsset)
+            ]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Finds the Markdown pages under `docs/`. `build.rs` includes this file to compile the page
+//! list into the crate, and the preview uses it to pick up new pages at runtime, so both agree
+//! on slugs. It only depends on `std` for that reason.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const DOCS_DIR: &str = "docs";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPage {
+    pub slug: String,
+    /// Relative to the crate root, with forward slashes: `docs/guides/local-manga.md`.
+    pub path: String,
+}
+
+impl DiscoveredPage {
+    /// Where the page's file is, given the directory [`discover_pages`] searched.
+    pub fn file(&self, docs_root: &Path) -> PathBuf {
+        let relative = self.path.strip_prefix(DOCS_DIR).unwrap_or(&self.path);
+        docs_root.join(relative.trim_start_matches('/'))
+    }
+}
+
+/// Every `.md` file under `docs_root`, sorted by slug. The 404 page is included.
+///
+/// Paths are labelled `docs/...` wherever the directory actually is, since they name the file
+/// in the docs repository.
+pub fn discover_pages(docs_root: &Path) -> io::Result<Vec<DiscoveredPage>> {
+    let mut files = Vec::new();
+    collect_markdown_files(docs_root, &mut files)?;
+
+    let mut pages: Vec<DiscoveredPage> = files
+        .iter()
+        .map(|file| {
+            let relative = file
+                .strip_prefix(docs_root)
+                .expect("walked paths stay under the docs root");
+            DiscoveredPage {
+                slug: slug_for_relative_path(relative),
+                path: format!("{DOCS_DIR}/{}", to_forward_slashes(relative)),
+            }
+        })
+        .collect();
+    pages.sort_by(|a, b| a.slug.cmp(&b.slug));
+    Ok(pages)
+}
+
+fn collect_markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_markdown_files(&path, files)?;
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `index.md` maps to its directory, every other file to its path without the extension.
+pub fn slug_for_relative_path(relative: &Path) -> String {
+    let without_extension = to_forward_slashes(&relative.with_extension(""));
+    if without_extension == "index" {
+        return String::new();
+    }
+
+    without_extension
+        .strip_suffix("/index")
+        .map(str::to_string)
+        .unwrap_or(without_extension)
+}
+
+fn to_forward_slashes(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_files_to_slugs() {
+        assert_eq!(slug_for_relative_path(Path::new("index.md")), "");
+        assert_eq!(
+            slug_for_relative_path(Path::new("guides/index.md")),
+            "guides"
+        );
+        assert_eq!(
+            slug_for_relative_path(Path::new("guides/local-manga.md")),
+            "guides/local-manga"
+        );
+
+        let docs_root = Path::new(env!("CARGO_MANIFEST_DIR")).join(DOCS_DIR);
+        let pages = discover_pages(&docs_root).expect("docs dir");
+        assert!(pages.iter().all(|page| page.file(&docs_root).is_file()));
+        let compiled: Vec<&str> = crate::all_pages().iter().map(|page| page.slug).collect();
+        let discovered: Vec<&str> = pages
+            .iter()
+            .map(|page| page.slug.as_str())
+            .filter(|slug| *slug != crate::not_found_page().slug)
+            .collect();
+        assert_eq!(discovered, compiled);
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    NavSection, SourcePage, build_aliases, build_nav_sections,
+    layout::{Chrome, SearchSource, render_document, render_redirect_html},
+    not_found_page, parse_doc,
+    render::{RenderedPage, docs_path_for_slug, render_source},
+    search::SearchIndex,
+    sitemap::{SitemapEntry, robots_txt, sitemap_xml},
+};
+
+pub const DEFAULT_OUT_DIR: &str = "target/docs-site";
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+pub const SITEMAP_FILE: &str = "sitemap.xml";
+/// What the export writes at the output root besides pages. No alias may start with one, or
+/// its redirect would overwrite or collide with it.
+pub const RESERVED_OUTPUTS: &[&str] = &[
+    "index.html",
+    "404.html",
+    "assets",
+    "robots.txt",
+    SEARCH_INDEX_FILE,
+    SITEMAP_FILE,
+];
+
+/// Where [`export_site`] reads from and writes to.
+#[derive(Debug, Clone, Copy)]
+pub struct ExportOptions<'a> {
+    pub out_dir: &'a Path,
+    /// Copied to `assets/` in the output when it exists.
+    pub assets_dir: &'a Path,
+    /// Public origin of the site, like `https://docs.example.com`. Adds canonical links and
+    /// absolute `og:image` URLs, and is needed for `sitemap.xml`.
+    pub base_url: Option<&'a str>,
+    /// Replaces the compiled-in page template.
+    pub template: Option<&'a str>,
+}
+
+/// What [`export_site`] wrote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub pages: usize,
+    pub redirects: usize,
+    pub assets: usize,
+    /// `false` when no base URL was given.
+    pub sitemap: bool,
+}
+
+/// Renders `pages`, the 404 page and the assets into a static site:
+///
+/// - the docs home becomes `index.html`, other pages `<slug>/index.html`
+/// - each `aliases`/`redirect_from` slug gets an `<alias>/index.html` that forwards to its page
+/// - the 404 page becomes `404.html`, which most static hosts serve for unknown paths
+/// - the assets directory, if there is one, is copied to `assets/`
+/// - the search index is written to `search-index.json` for the search box
+/// - `robots.txt` is written, and `sitemap.xml` too when there is a base URL
+///
+/// Links are rewritten to relative paths ending in `index.html`, so the output also works
+/// when opened straight from disk. The 404 page is taken from `pages` when it is there and
+/// is the compiled-in one otherwise.
+pub fn export_site(pages: &[SourcePage], options: &ExportOptions<'_>) -> io::Result<ExportSummary> {
+    let out_dir = options.out_dir;
+    let base_url = options.base_url.map(|base| base.trim_end_matches('/'));
+    let not_found_slug = not_found_page().slug;
+    let (not_found, pages): (Vec<&SourcePage>, Vec<&SourcePage>) =
+        pages.iter().partition(|page| page.slug == not_found_slug);
+    let not_found = not_found.first().map_or_else(
+        || SourcePage::from(not_found_page()),
+        |page| (*page).clone(),
+    );
+
+    let pairs = || {
+        pages
+            .iter()
+            .map(|page| (page.slug.as_str(), page.source.as_str()))
+    };
+    let site = ExportSite {
+        sections: build_nav_sections(pairs()),
+        base_url,
+        template: options.template,
+    };
+
+    fs::create_dir_all(out_dir)?;
+
+    for page in &pages {
+        let prefix = "../".repeat(page_depth(&page.slug));
+        let html = site.render_page_html(page, &format!("{prefix}{SEARCH_INDEX_FILE}"));
+        let html = relativize_links(&html, &prefix);
+        write_file(&out_dir.join(page_output_path(&page.slug)), &html)?;
+    }
+
+    // Old slugs get a page that forwards to the new one.
+    let aliases = build_aliases(pairs());
+    for (alias, slug) in &aliases {
+        let Some(page) = pages.iter().find(|page| &page.slug == slug) else {
+            continue;
+        };
+        let target = match base_url {
+            Some(base) => page_url(base, slug),
+            None => format!(
+                "{}{}",
+                "../".repeat(page_depth(alias)),
+                export_path(&docs_path_for_slug(slug))
+            ),
+        };
+        let html = render_redirect_html(&target, parse_doc(&page.source).title());
+        write_file(&out_dir.join(page_output_path(alias)), &html)?;
+    }
+
+    // Hosts serve 404.html from whatever path was requested, so its links must not be
+    // relative to its own location.
+    let html = site.render_page_html(&not_found, &format!("/{SEARCH_INDEX_FILE}"));
+    let html = rewrite_links(&html, |target| format!("/{}", export_path(target)));
+    write_file(&out_dir.join("404.html"), &html)?;
+
+    // Entry URLs are relative to the index file, which sits at the output root.
+    let search_json = SearchIndex::build(pairs()).to_json(export_path);
+    write_file(&out_dir.join(SEARCH_INDEX_FILE), &search_json)?;
+
+    // Sitemap URLs must be absolute, so the sitemap needs to know where the site lives.
+    let sitemap_url = base_url.map(|base| format!("{base}/{SITEMAP_FILE}"));
+    if let Some(base) = base_url {
+        let entries: Vec<SitemapEntry> = pages
+            .iter()
+            .filter_map(|page| {
+                SitemapEntry::from_source(&page.slug, &page.source, page.last_modified.as_deref())
+            })
+            .collect();
+        let xml = sitemap_xml(&entries, |slug| page_url(base, slug));
+        write_file(&out_dir.join(SITEMAP_FILE), &xml)?;
+    }
+    write_file(
+        &out_dir.join("robots.txt"),
+        &robots_txt(sitemap_url.as_deref()),
+    )?;
+
+    let assets = if options.assets_dir.is_dir() {
+        copy_dir(options.assets_dir, &out_dir.join("assets"))?
+    } else {
+        0
+    };
+
+    Ok(ExportSummary {
+        pages: pages.len(),
+        redirects: aliases.len(),
+        assets,
+        sitemap: base_url.is_some(),
+    })
+}
+
+/// What every exported page shares.
+struct ExportSite<'a> {
+    sections: Vec<NavSection>,
+    base_url: Option<&'a str>,
+    template: Option<&'a str>,
+}
+
+impl ExportSite<'_> {
+    fn render_page_html(&self, page: &SourcePage, search_index_url: &str) -> String {
+        let page_url = self.base_url.map(|base| page_url(base, &page.slug));
+        let chrome = Chrome {
+            search: Some(SearchSource::Index(search_index_url)),
+            base_url: self.base_url,
+            page_url: page_url.as_deref(),
+            template: self.template,
+            ..Chrome::default()
+        };
+        render_document(
+            &page.slug,
+            Some(&page.path),
+            &render_source_page(page),
+            &self.sections,
+            &chrome,
+        )
+    }
+}
+
+/// Like [`crate::render::render_page`], for a page read at runtime.
+fn render_source_page(page: &SourcePage) -> RenderedPage {
+    let mut rendered = render_source(&page.source);
+    if rendered.modified_time.is_none() {
+        rendered.modified_time = page.last_modified.clone();
+    }
+    rendered
+}
+
+/// Public URL of an exported page. Hosts serve `<slug>/index.html` at `<slug>/`.
+pub fn page_url(base_url: &str, slug: &str) -> String {
+    if slug.is_empty() {
+        format!("{base_url}/")
+    } else {
+        format!("{base_url}/{slug}/")
+    }
+}
+
+fn page_output_path(slug: &str) -> PathBuf {
+    if slug.is_empty() {
+        PathBuf::from("index.html")
+    } else {
+        Path::new(slug).join("index.html")
+    }
+}
+
+/// How many directories deep a page's `index.html` sits below the output root.
+fn page_depth(slug: &str) -> usize {
+    if slug.is_empty() {
+        0
+    } else {
+        slug.split('/').count()
+    }
+}
+
+fn relativize_links(html: &str, prefix: &str) -> String {
+    rewrite_links(html, |target| format!("{prefix}{}", export_path(target)))
+}
+
+/// Maps a site URL (`/docs/guides/novels#setup`, `/assets/a.png`) to its path in the export.
+fn export_path(target: &str) -> String {
+    if let Some(asset) = target.strip_prefix("/assets/") {
+        return format!("assets/{asset}");
+    }
+
+    let rest = target.strip_prefix("/docs").unwrap_or(target);
+    let (path, fragment) = match rest.find(['#', '?']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let slug = path.trim_matches('/');
+    if slug.is_empty() {
+        format!("index.html{fragment}")
+    } else {
+        format!("{slug}/index.html{fragment}")
+    }
+}
+
+/// Rewrites every `href="/docs..."`, `href="/assets/..."` and `src="/assets/..."` attribute.
+fn rewrite_links(html: &str, rewrite: impl Fn(&str) -> String) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(index) = rest.find("=\"/") {
+        let (before, after) = rest.split_at(index + 2);
+        output.push_str(before);
+
+        let end = after.find('"').unwrap_or(after.len());
+        let target = &after[..end];
+        let is_site_link = before.ends_with("href=\"") || before.ends_with("src=\"");
+        if is_site_link && (is_docs_url(target) || target.starts_with("/assets/")) {
+            output.push_str(&rewrite(target));
+        } else {
+            output.push_str(target);
+        }
+        rest = &after[end..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn is_docs_url(target: &str) -> bool {
+    target
+        .strip_prefix("/docs")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '#', '?']))
+}
+
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_site_links_to_export_paths() {
+        let html = "<a href=\"/docs\">Home</a><a href=\"/docs/guides/novels#setup\">Novels</a>\
+            <img src=\"/assets/a.png\" /><a href=\"/docsearch\">Other</a>";
+
+        assert_eq!(
+            relativize_links(html, "../../"),
+            "<a href=\"../../index.html\">Home</a><a href=\"../../guides/novels/index.html#setup\">Novels</a>\
+            <img src=\"../../assets/a.png\" /><a href=\"/docsearch\">Other</a>"
+        );
+        assert_eq!(page_depth("guides/novels"), 2);
+        assert_eq!(
+            page_url("https://docs.example.com", ""),
+            "https://docs.example.com/"
+        );
+    }
+
+    #[test]
+    fn exports_without_an_assets_directory_and_skips_reserved_aliases() {
+        let dir = std::env::temp_dir().join(format!("export-site-{}", std::process::id()));
+        let page = SourcePage {
+            slug: "guides/anki".to_string(),
+            path: "docs/guides/anki.md".to_string(),
+            source: "---\ntitle: Anki\naliases: [sitemap.xml, assets/anki, guides/old-anki]\n---\n"
+                .to_string(),
+            last_modified: None,
+        };
+
+        let summary = export_site(
+            &[page],
+            &ExportOptions {
+                out_dir: &dir.join("out"),
+                assets_dir: &dir.join("missing-assets"),
+                base_url: None,
+                template: None,
+            },
+        );
+        let wrote_sitemap_redirect = dir.join("out/sitemap.xml").exists();
+        let _ = fs::remove_dir_all(&dir);
+
+        let summary = summary.expect("export");
+        assert_eq!(
+            (summary.pages, summary.redirects, summary.assets),
+            (1, 1, 0)
+        );
+        assert!(!wrote_sitemap_redirect);
+    }
+}
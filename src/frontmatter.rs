@@ -0,0 +1,585 @@
+mod toml;
+mod yaml;
+
+use std::{collections::BTreeMap, fmt};
+
+use toml::parse_toml;
+use yaml::parse_yaml;
+
+const UTF8_BOM: char = '\u{feff}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// `---` delimiters.
+    Yaml,
+    /// `+++` delimiters.
+    Toml,
+}
+
+/// A page split into its frontmatter block and Markdown body.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SplitSource<'a> {
+    pub format: Format,
+    pub block: &'a str,
+    pub body: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SplitError {
+    /// The first line is not a `---` or `+++` delimiter.
+    Missing,
+    /// The opening delimiter has no matching closing line.
+    Unclosed(Format),
+}
+
+/// Finds the frontmatter block at the top of `source`.
+///
+/// A leading UTF-8 BOM is skipped, delimiter lines may end in `\r\n` or trailing whitespace,
+/// and a closing delimiter on the last line needs no newline after it.
+pub(crate) fn split_source(source: &str) -> Result<SplitSource<'_>, SplitError> {
+    let source = source.strip_prefix(UTF8_BOM).unwrap_or(source);
+    let (first_line, mut rest) = source.split_once('\n').unwrap_or((source, ""));
+    let (format, delimiter) = match first_line.trim_end() {
+        "---" => (Format::Yaml, "---"),
+        "+++" => (Format::Toml, "+++"),
+        _ => return Err(SplitError::Missing),
+    };
+
+    let block_start = rest;
+    loop {
+        let (line, after) = rest.split_once('\n').unwrap_or((rest, ""));
+        if line.trim_end() == delimiter {
+            let block_len = block_start.len() - rest.len();
+            return Ok(SplitSource {
+                format,
+                block: &block_start[..block_len],
+                body: after,
+            });
+        }
+        if after.is_empty() {
+            return Err(SplitError::Unclosed(format));
+        }
+        rest = after;
+    }
+}
+
+impl SplitSource<'_> {
+    /// Parses the block; line numbers start at 2 because the opening delimiter is line 1.
+    pub fn parse(&self) -> (Frontmatter, Vec<Diagnostic>) {
+        match self.format {
+            Format::Yaml => Frontmatter::from_yaml_with_diagnostics(self.block, 2),
+            Format::Toml => Frontmatter::from_toml_with_diagnostics(self.block, 2),
+        }
+    }
+}
+
+/// Typed metadata from the frontmatter block at the top of a page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub nav_label: Option<String>,
+    pub nav_hint: Option<String>,
+    pub nav_section: Option<String>,
+    pub nav_order: Option<i64>,
+    pub tags: Vec<String>,
+    /// Old slugs that redirect here, from `aliases` and `redirect_from`.
+    pub aliases: Vec<String>,
+    /// Keeps the page out of `sitemap.xml`. Drafts are otherwise built, listed and searchable
+    /// like any other page.
+    pub draft: bool,
+    pub last_reviewed: Option<String>,
+    pub platforms: Vec<String>,
+    pub min_version: Option<String>,
+    pub layout: Option<String>,
+    /// Social preview image for `og:image`, as a URL or an `/assets/...` path.
+    pub image: Option<String>,
+    /// Every key not covered by a field above, so new metadata does not need a parser change.
+    pub extra: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            Value::Map(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl Frontmatter {
+    /// Parses the YAML between the `---` delimiters, without the delimiters themselves. Any
+    /// YAML 1.2 document that is a mapping works, anchors and merge keys included. A block
+    /// that fails to parse gives empty frontmatter.
+    pub fn from_yaml(block: &str) -> Self {
+        Self::from_yaml_with_diagnostics(block, 1).0
+    }
+
+    /// Like [`Frontmatter::from_yaml`], but also reports syntax errors and values with the
+    /// wrong shape. `first_line` is the line number of the block's first line in the page
+    /// source.
+    pub fn from_yaml_with_diagnostics(block: &str, first_line: usize) -> (Self, Vec<Diagnostic>) {
+        let (entries, diagnostics) = parse_yaml(block, first_line);
+        Self::from_entries(entries, diagnostics)
+    }
+
+    /// Parses the TOML between `+++` delimiters into the same model as [`Frontmatter::from_yaml`].
+    pub fn from_toml(block: &str) -> Self {
+        Self::from_toml_with_diagnostics(block, 1).0
+    }
+
+    pub fn from_toml_with_diagnostics(block: &str, first_line: usize) -> (Self, Vec<Diagnostic>) {
+        let (entries, diagnostics) = parse_toml(block, first_line);
+        Self::from_entries(entries, diagnostics)
+    }
+
+    fn from_entries(
+        entries: Vec<Entry>,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> (Self, Vec<Diagnostic>) {
+        let mut frontmatter = Frontmatter::default();
+        for entry in entries {
+            if let Err(problem) = frontmatter.set(entry.key.clone(), entry.node) {
+                diagnostics.push(Diagnostic::warning(
+                    entry.line,
+                    entry.column,
+                    format!("`{}` {problem}", entry.key),
+                ));
+            }
+        }
+        (frontmatter, diagnostics)
+    }
+
+    /// Stores one top-level key, or describes why its value has the wrong shape for a typed field.
+    fn set(&mut self, key: String, node: Node) -> FieldResult<()> {
+        match key.as_str() {
+            "title" => self.title = string_field(node)?,
+            "description" => self.description = string_field(node)?,
+            "nav_label" => self.nav_label = string_field(node)?,
+            "nav_hint" => self.nav_hint = string_field(node)?,
+            "nav_section" => self.nav_section = string_field(node)?,
+            "nav_order" => match Value::from(node) {
+                Value::Integer(order) => self.nav_order = Some(order),
+                Value::Null => self.nav_order = None,
+                _ => return Err("should be a whole number"),
+            },
+            "tags" => self.tags = string_list_field(node)?,
+            "aliases" | "redirect_from" => self.aliases.extend(string_list_field(node)?),
+            "draft" => match Value::from(node) {
+                Value::Bool(draft) => self.draft = draft,
+                Value::Null => self.draft = false,
+                _ => return Err("should be `true` or `false`"),
+            },
+            "last_reviewed" => self.last_reviewed = string_field(node)?,
+            "platforms" => self.platforms = string_list_field(node)?,
+            "min_version" => self.min_version = string_field(node)?,
+            "layout" => self.layout = string_field(node)?,
+            "image" => self.image = string_field(node)?,
+            _ => {
+                self.extra.insert(key, Value::from(node));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a standalone TOML file, like the preview's `preview.toml`, the same way as `+++`
+/// frontmatter. Line numbers in the diagnostics count from the top of the file.
+pub fn parse_toml_document(text: &str) -> (BTreeMap<String, Value>, Vec<Diagnostic>) {
+    let (entries, diagnostics) = parse_toml(text, 1);
+    let values = entries
+        .into_iter()
+        .map(|entry| (entry.key, Value::from(entry.node)))
+        .collect();
+    (values, diagnostics)
+}
+
+/// `Ok` wraps the field value; `Err` describes why the node does not fit.
+type FieldResult<T> = Result<T, &'static str>;
+
+fn string_field(node: Node) -> FieldResult<Option<String>> {
+    match node {
+        Node::List(_) | Node::Map(_) => Err("should be a single value, not a list or mapping"),
+        scalar => Ok(scalar.into_string()),
+    }
+}
+
+fn string_list_field(node: Node) -> FieldResult<Vec<String>> {
+    match node {
+        Node::List(items) if items.iter().all(|item| matches!(item, Node::Scalar { .. })) => {
+            Ok(items.into_iter().filter_map(Node::into_string).collect())
+        }
+        Node::List(_) | Node::Map(_) => Err("should be a list of plain values"),
+        scalar => Ok(scalar.into_string().into_iter().collect()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The lenient parser reads the page, but probably not the way the author meant.
+    Warning,
+    /// The lenient parser drops or misreads part of the page.
+    Error,
+}
+
+/// A problem found in a page's frontmatter. `line` and `column` are 1-based and point into the
+/// whole Markdown source, not just the frontmatter block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Diagnostic {
+            line,
+            column,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Diagnostic {
+            line,
+            column,
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+/// Untyped parse tree. Scalars keep their source text so string fields like `min_version: 1.10`
+/// are not mangled by number parsing.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Scalar { text: String, plain: bool },
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    key: String,
+    node: Node,
+    line: usize,
+    column: usize,
+}
+
+impl Node {
+    fn null() -> Self {
+        Node::Scalar {
+            text: String::new(),
+            plain: true,
+        }
+    }
+
+    fn into_string(self) -> Option<String> {
+        match self {
+            Node::Scalar { text, plain: false } => Some(text),
+            Node::Scalar { text, plain: true } => {
+                (plain_scalar_value(text.clone()) != Value::Null).then_some(text)
+            }
+            Node::List(_) | Node::Map(_) => None,
+        }
+    }
+}
+
+impl From<Node> for Value {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Scalar { text, plain: false } => Value::String(text),
+            Node::Scalar { text, plain: true } => plain_scalar_value(text),
+            Node::List(items) => Value::List(items.into_iter().map(Value::from).collect()),
+            Node::Map(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, node)| (key, Value::from(node)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn plain_scalar_value(text: String) -> Value {
+    match text.as_str() {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+
+    if let Ok(integer) = text.parse::<i64>() {
+        return Value::Integer(integer);
+    }
+
+    let unsigned = text.trim_start_matches(['+', '-']);
+    if unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && unsigned.chars().any(|c| c.is_ascii_digit())
+        && let Ok(float) = text.parse::<f64>()
+    {
+        return Value::Float(float);
+    }
+
+    Value::String(text)
+}
+
+/// Where top-level `key` is written, followed by `separator` (`:` or `=`): the 0-based line,
+/// the 1-based column, and the plain scalar after it when there is one on the same line.
+/// Top-level keys are the ones indented like the block's first key, so a nested key of the
+/// same name is not mistaken for it.
+fn find_key_line<'a>(
+    block: &'a str,
+    key: &str,
+    separator: char,
+) -> Option<(usize, usize, Option<&'a str>)> {
+    let indent = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let top_level_indent = block
+        .lines()
+        .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map_or(0, indent);
+
+    block.lines().enumerate().find_map(|(index, line)| {
+        if indent(line) != top_level_indent {
+            return None;
+        }
+        let rest = [key.to_string(), format!("\"{key}\""), format!("'{key}'")]
+            .iter()
+            .find_map(|spelling| line[top_level_indent..].strip_prefix(spelling.as_str()))?;
+        let rest = rest
+            .trim_start_matches([' ', '\t'])
+            .strip_prefix(separator)?;
+        if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            return None;
+        }
+
+        let value = match rest.find(" #") {
+            Some(comment) => &rest[..comment],
+            None => rest,
+        }
+        .trim();
+        let is_plain =
+            !value.is_empty() && !value.starts_with(['&', '*', '!', '|', '>', '[', '{', '"', '\'']);
+        Some((index, top_level_indent + 1, is_plain.then_some(value)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_typed_fields_and_keeps_unknown_keys() {
+        let frontmatter = Frontmatter::from_yaml(
+            "title: \"Local manga\"\n\
+             tags: [manga, 'local files']\n\
+             aliases:\n  - guides/manga\n  - manga\n\
+             draft: true\n\
+             min_version: 1.10\n\
+             platforms:\n- android\n- windows\n\
+             sources:\n  - name: Suwayomi\n    official: false\n\
+             weight: 3 # comment\n",
+        );
+
+        assert_eq!(frontmatter.title.as_deref(), Some("Local manga"));
+        assert_eq!(frontmatter.tags, ["manga", "local files"]);
+        assert_eq!(frontmatter.aliases, ["guides/manga", "manga"]);
+        assert!(frontmatter.draft);
+        assert_eq!(frontmatter.min_version.as_deref(), Some("1.10"));
+        assert_eq!(frontmatter.platforms, ["android", "windows"]);
+        assert_eq!(frontmatter.extra["weight"], Value::Integer(3));
+
+        let sources = frontmatter.extra["sources"]
+            .as_list()
+            .expect("sources is a list");
+        let source = sources[0].as_map().expect("source is a map");
+        assert_eq!(source["name"].as_str(), Some("Suwayomi"));
+        assert_eq!(source["official"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn parses_multi_line_values() {
+        let frontmatter = Frontmatter::from_yaml(
+            "description: >\n  Folder layouts\n  and formats.\n\
+             notes: |-\n  line one\n    indented\n\
+             nav_hint: wraps\n  onto two lines\n",
+        );
+
+        assert_eq!(
+            frontmatter.description.as_deref(),
+            Some("Folder layouts and formats.\n")
+        );
+        assert_eq!(
+            frontmatter.extra["notes"].as_str(),
+            Some("line one\n  indented")
+        );
+        assert_eq!(
+            frontmatter.nav_hint.as_deref(),
+            Some("wraps onto two lines")
+        );
+    }
+
+    #[test]
+    fn parses_full_yaml() {
+        let (frontmatter, diagnostics) = Frontmatter::from_yaml_with_diagnostics(
+            "title: \"A title that\n  wraps\"\n\
+             defaults: &defaults\n  official: true\n\
+             sources:\n  - name: Suwayomi\n    <<: *defaults\n\
+             grid:\n  - - a\n    - b\n\
+             nav_label: !label 'Tagged'\n\
+             ...\n",
+            2,
+        );
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(frontmatter.title.as_deref(), Some("A title that wraps"));
+        assert_eq!(frontmatter.nav_label.as_deref(), Some("Tagged"));
+
+        let sources = frontmatter.extra["sources"].as_list().expect("sources");
+        let source = sources[0].as_map().expect("source is a map");
+        assert_eq!(source["official"].as_bool(), Some(true));
+
+        let grid = frontmatter.extra["grid"].as_list().expect("grid");
+        let row: Vec<_> = grid[0].as_list().expect("a nested list").to_vec();
+        assert_eq!(row, [Value::String("a".into()), Value::String("b".into())]);
+
+        let (frontmatter, diagnostics) =
+            Frontmatter::from_yaml_with_diagnostics("title: Hi\nnav_hint: *missing\n", 2);
+        assert_eq!(frontmatter, Frontmatter::default());
+        assert_eq!(diagnostics, [Diagnostic::error(3, 11, "unknown anchor")]);
+
+        let (frontmatter, diagnostics) = Frontmatter::from_yaml_with_diagnostics(
+            "  sources:\n    draft: yes\n  draft: maybe\n",
+            2,
+        );
+        assert!(!frontmatter.draft);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::warning(
+                4,
+                3,
+                "`draft` should be `true` or `false`"
+            )]
+        );
+    }
+
+    #[test]
+    fn parses_toml_tables_and_multi_line_strings() {
+        let (frontmatter, diagnostics) = Frontmatter::from_toml_with_diagnostics(
+            "title = 'Local manga'\n\
+             draft = false\n\
+             nav_order = 1_000\n\
+             min_version = 1.10\n\
+             notes = \"\"\"\nfirst\nsecond\"\"\"\n\
+             platforms = [\n  \"android\", # phones\n  \"linux\",\n]\n\
+             social.image = \"/assets/cover.png\"\n\
+             [[sources]]\nname = \"Suwayomi\"\n\
+             [[sources]]\nname = \"Local\"\n",
+            2,
+        );
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(frontmatter.title.as_deref(), Some("Local manga"));
+        assert_eq!(frontmatter.nav_order, Some(1000));
+        assert_eq!(frontmatter.min_version.as_deref(), Some("1.10"));
+        assert_eq!(frontmatter.platforms, ["android", "linux"]);
+        assert_eq!(frontmatter.extra["notes"].as_str(), Some("first\nsecond"));
+
+        let social = frontmatter.extra["social"]
+            .as_map()
+            .expect("social is a table");
+        assert_eq!(social["image"].as_str(), Some("/assets/cover.png"));
+
+        let sources = frontmatter.extra["sources"]
+            .as_list()
+            .expect("sources is an array");
+        let names: Vec<_> = sources
+            .iter()
+            .filter_map(|source| source.as_map()?.get("name")?.as_str())
+            .collect();
+        assert_eq!(names, ["Suwayomi", "Local"]);
+    }
+
+    #[test]
+    fn parses_standalone_toml_documents() {
+        let (values, diagnostics) = parse_toml_document(
+            "bind = \"0.0.0.0:8080\"\nopen = true\npoll_interval = 1_000\n\
+             root = 'C:\\docs'\n\n[watch]\nmode = \"poll\"\n",
+        );
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(values["bind"].as_str(), Some("0.0.0.0:8080"));
+        assert_eq!(values["open"].as_bool(), Some(true));
+        assert_eq!(values["poll_interval"].as_i64(), Some(1000));
+        assert_eq!(values["root"].as_str(), Some("C:\\docs"));
+        let watch = values["watch"].as_map().expect("watch is a table");
+        assert_eq!(watch["mode"].as_str(), Some("poll"));
+
+        let (values, diagnostics) = parse_toml_document("bind = \"x\"\n\n[watch]\n  oops\n");
+        assert!(values.is_empty());
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::error(4, 7, "key with no value, expected `=`")]
+        );
+    }
+}
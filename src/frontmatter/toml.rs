@@ -0,0 +1,83 @@
+use ::toml::{Table, Value as Toml};
+
+use super::{Diagnostic, Entry, Node, find_key_line, plain_scalar_value};
+
+/// Parses a `+++` block, or a whole TOML file, with the `toml` crate into its top-level
+/// entries. A block that is not valid TOML gives no entries and one error.
+pub(super) fn parse_toml(block: &str, first_line: usize) -> (Vec<Entry>, Vec<Diagnostic>) {
+    let table = match block.parse::<Table>() {
+        Ok(table) => table,
+        Err(error) => {
+            let offset = error.span().map_or(0, |span| span.start).min(block.len());
+            let before = &block[..offset];
+            let line = before.matches('\n').count();
+            let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+            let column = before[line_start..].chars().count() + 1;
+            return (
+                Vec::new(),
+                vec![Diagnostic::error(
+                    first_line + line,
+                    column,
+                    error.message(),
+                )],
+            );
+        }
+    };
+
+    let entries = table
+        .into_iter()
+        .map(|(key, value)| {
+            let (line, column, raw) = find_key_line(block, &key, '=')
+                .map_or((first_line, 1, None), |(index, column, raw)| {
+                    (first_line + index, column, raw)
+                });
+            Entry {
+                node: node_from_toml(value, raw),
+                key,
+                line,
+                column,
+            }
+        })
+        .collect();
+    (entries, Vec::new())
+}
+
+/// Converts a parsed value, keeping `raw` as the text of a number when it reads back as the
+/// same value, like YAML does.
+fn node_from_toml(value: Toml, raw: Option<&str>) -> Node {
+    let plain = |text: String| {
+        let text = match raw {
+            Some(raw)
+                if plain_scalar_value(raw.to_string()) == plain_scalar_value(text.clone()) =>
+            {
+                raw.to_string()
+            }
+            _ => text,
+        };
+        Node::Scalar { text, plain: true }
+    };
+
+    match value {
+        Toml::String(text) => Node::Scalar { text, plain: false },
+        Toml::Integer(value) => plain(value.to_string()),
+        // `Debug` keeps the `.0` that tells `1.0` from `1`.
+        Toml::Float(value) => plain(format!("{value:?}")),
+        Toml::Boolean(value) => plain(value.to_string()),
+        Toml::Datetime(value) => Node::Scalar {
+            text: value.to_string(),
+            plain: false,
+        },
+        Toml::Array(items) => Node::List(
+            items
+                .into_iter()
+                .map(|item| node_from_toml(item, None))
+                .collect(),
+        ),
+        Toml::Table(table) => Node::Map(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, node_from_toml(value, None)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,133 @@
+use serde_yaml::Value as Yaml;
+
+use super::{Diagnostic, Entry, Node, find_key_line, plain_scalar_value};
+
+/// Parses a `---` block with `serde_yaml` into its top-level entries. A block that is not
+/// valid YAML gives no entries and one error; so does one that is not a mapping.
+pub(super) fn parse_yaml(block: &str, first_line: usize) -> (Vec<Entry>, Vec<Diagnostic>) {
+    let mut document: Yaml = match serde_yaml::from_str(block) {
+        Ok(document) => document,
+        Err(error) => {
+            let message = error.to_string();
+            let (line, column) = error_position(&error, &message);
+            // The position is reported separately, so drop libyaml's own "at line ..." part.
+            let message = message.split(" at line ").next().unwrap_or_default();
+            return (
+                Vec::new(),
+                vec![Diagnostic::error(first_line + line - 1, column, message)],
+            );
+        }
+    };
+    if let Err(error) = document.apply_merge() {
+        return (
+            Vec::new(),
+            vec![Diagnostic::error(first_line, 1, error.to_string())],
+        );
+    }
+
+    let mapping = match document {
+        Yaml::Mapping(mapping) => mapping,
+        Yaml::Null => return (Vec::new(), Vec::new()),
+        _ => {
+            return (
+                Vec::new(),
+                vec![Diagnostic::error(
+                    first_line,
+                    1,
+                    "expected `key: value` pairs at the top level",
+                )],
+            );
+        }
+    };
+
+    let mut entries = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (key, value) in mapping {
+        let Some(key) = key_text(&key) else {
+            diagnostics.push(Diagnostic::warning(
+                first_line,
+                1,
+                "skipped a key that is a list or mapping",
+            ));
+            continue;
+        };
+        let (line, column, raw) = find_key_line(block, &key, ':')
+            .map_or((first_line, 1, None), |(index, column, raw)| {
+                (first_line + index, column, raw)
+            });
+        entries.push(Entry {
+            node: node_from_yaml(value, raw),
+            key,
+            line,
+            column,
+        });
+    }
+    (entries, diagnostics)
+}
+
+/// Where a syntax error is, as a 1-based line and column within the block. libyaml notices
+/// some mistakes only on a later line, like a key missing its `:` once the next key starts,
+/// or an unclosed quote at the end of the block. Its message then also names where the
+/// construct it was reading began (`..., while scanning a simple key at line 2 column 1`),
+/// which is where the mistake is.
+fn error_position(error: &serde_yaml::Error, message: &str) -> (usize, usize) {
+    let started_at = message
+        .split_once(", while ")
+        .and_then(|(_, context)| context.rsplit_once(" at line "))
+        .and_then(|(_, position)| {
+            let (line, column) = position.split_once(" column ")?;
+            Some((line.parse().ok()?, column.trim().parse().ok()?))
+        });
+    started_at
+        .or_else(|| {
+            let location = error.location()?;
+            Some((location.line(), location.column()))
+        })
+        .unwrap_or((1, 1))
+}
+
+/// Converts a parsed value. `raw` is the scalar as written in the source, used as the text
+/// of numbers and booleans when it reads back as the same value: `min_version: 1.10` stays
+/// `1.10` for string fields instead of becoming `1.1`.
+fn node_from_yaml(value: Yaml, raw: Option<&str>) -> Node {
+    let plain = |text: String| {
+        let text = match raw {
+            Some(raw)
+                if plain_scalar_value(raw.to_string()) == plain_scalar_value(text.clone()) =>
+            {
+                raw.to_string()
+            }
+            _ => text,
+        };
+        Node::Scalar { text, plain: true }
+    };
+
+    match value {
+        Yaml::Null => Node::null(),
+        Yaml::Bool(value) => plain(value.to_string()),
+        Yaml::Number(number) => plain(number.to_string()),
+        Yaml::String(text) => Node::Scalar { text, plain: false },
+        Yaml::Sequence(items) => Node::List(
+            items
+                .into_iter()
+                .map(|item| node_from_yaml(item, None))
+                .collect(),
+        ),
+        Yaml::Mapping(mapping) => Node::Map(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| Some((key_text(&key)?, node_from_yaml(value, None))))
+                .collect(),
+        ),
+        Yaml::Tagged(tagged) => node_from_yaml(tagged.value, raw),
+    }
+}
+
+fn key_text(key: &Yaml) -> Option<String> {
+    match key {
+        Yaml::String(key) => Some(key.clone()),
+        Yaml::Number(key) => Some(key.to_string()),
+        Yaml::Bool(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
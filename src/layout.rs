@@ -0,0 +1,317 @@
+use crate::{
+    NavSection, not_found_page,
+    render::{
+        RenderedPage, escape_html, render_adjacent_pages_html, render_breadcrumbs_html,
+        render_sidebar_html, render_toc_html,
+    },
+};
+
+/// The site chrome around every page: nav bar, sidebar, content card and TOC panel.
+/// `{{PLACEHOLDERS}}` are filled in by [`render_document`].
+pub const PAGE_TEMPLATE: &str = include_str!("../templates/page.html");
+
+pub const DOCS_REPO_BASE_URL: &str = "https://github.com/KolbyML/Manatan-Documentation";
+
+const SITE_NAME: &str = "Manatan Docs";
+
+/// The parts of the page chrome that differ between the preview and the exported site.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chrome<'a> {
+    /// Shown in the nav bar and after the page title. Defaults to "Manatan Docs".
+    pub brand: Option<&'a str>,
+    /// HTML shown above the content grid.
+    pub banner_html: &'a str,
+    /// HTML inserted just before `</body>`, for extra scripts.
+    pub body_end_html: &'a str,
+    /// Where the nav bar search box gets results from. No search box when `None`.
+    pub search: Option<SearchSource<'a>>,
+    /// Public origin of the site, like `https://docs.example.com`. `/assets/...` images are
+    /// made absolute against it for `og:image`.
+    pub base_url: Option<&'a str>,
+    /// Public URL of this page, for the canonical link and `og:url`.
+    pub page_url: Option<&'a str>,
+    /// Replaces [`PAGE_TEMPLATE`]. The preview reads the template from disk, so edits to it
+    /// show up without a rebuild.
+    pub template: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSource<'a> {
+    /// A server route answering `?q=` with ranked results, like the preview's `/search`.
+    Endpoint(&'a str),
+    /// URL of a file written by `SearchIndex::to_json`, searched in the browser.
+    Index(&'a str),
+}
+
+/// The parts of a page that change from one page to the next. The preview swaps them into an
+/// open tab when a file changes, instead of reloading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageParts {
+    /// The full `<title>`, brand included.
+    pub title: String,
+    pub sidebar_html: String,
+    pub content_html: String,
+    pub toc_html: String,
+}
+
+impl PageParts {
+    /// The parts as one HTML fragment: a `<title>`, then elements with the `sidebar`,
+    /// `content` and `toc` classes the template gives their places on the page.
+    pub fn to_fragment_html(&self) -> String {
+        format!(
+            "<title>{}</title><aside class=\"sidebar\">{}</aside><article class=\"content\">{}</article><aside class=\"toc\">{}</aside>",
+            escape_html(&self.title),
+            self.sidebar_html,
+            self.content_html,
+            self.toc_html
+        )
+    }
+}
+
+/// Renders the title, sidebar, content card and TOC for a page.
+///
+/// `markdown_path` is the page's file relative to the crate root; when given, the page ends
+/// with "Edit this page" and "Open an issue" links to the docs repository.
+pub fn render_page_parts(
+    page_slug: &str,
+    markdown_path: Option<&str>,
+    rendered: &RenderedPage,
+    sections: &[NavSection],
+    brand: &str,
+) -> PageParts {
+    let title = if rendered.title.trim().is_empty() {
+        brand.to_string()
+    } else {
+        format!("{} - {brand}", rendered.title.trim())
+    };
+
+    let mut content_html = render_breadcrumbs_html(sections, page_slug);
+    content_html.push_str(&rendered.body_html);
+    content_html.push_str(&render_adjacent_pages_html(sections, page_slug));
+    if let Some(markdown_path) = markdown_path {
+        content_html.push_str(&render_fix_prompt_html(markdown_path));
+    }
+
+    PageParts {
+        title,
+        sidebar_html: render_sidebar_html(sections, page_slug),
+        content_html,
+        toc_html: render_toc_html(&rendered.headings),
+    }
+}
+
+/// Wraps a rendered page in [`PAGE_TEMPLATE`]; see [`render_page_parts`].
+pub fn render_document(
+    page_slug: &str,
+    markdown_path: Option<&str>,
+    rendered: &RenderedPage,
+    sections: &[NavSection],
+    chrome: &Chrome<'_>,
+) -> String {
+    let brand = chrome.brand.unwrap_or(SITE_NAME);
+    let parts = render_page_parts(page_slug, markdown_path, rendered, sections, brand);
+
+    chrome
+        .template
+        .unwrap_or(PAGE_TEMPLATE)
+        .replace("{{TITLE}}", &escape_html(&parts.title))
+        .replace(
+            "{{HEAD}}",
+            &render_head_meta_html(page_slug, rendered, chrome),
+        )
+        .replace("{{BRAND}}", &escape_html(brand))
+        .replace("{{SEARCH}}", &render_search_box_html(chrome.search))
+        .replace("{{BANNER}}", chrome.banner_html)
+        .replace("{{SIDEBAR}}", &parts.sidebar_html)
+        .replace("{{TOC}}", &parts.toc_html)
+        .replace("{{BODY_END}}", chrome.body_end_html)
+        .replace("{{CONTENT}}", &parts.content_html)
+}
+
+/// Description, canonical link and Open Graph / Twitter card tags for the `<head>`.
+fn render_head_meta_html(page_slug: &str, rendered: &RenderedPage, chrome: &Chrome<'_>) -> String {
+    let base_url = chrome.base_url.map(|base| base.trim_end_matches('/'));
+    let title = rendered.title.trim();
+    let description = rendered.description.trim();
+    let mut tags = Vec::new();
+    let mut meta = |attribute: &str, key: &str, value: &str| {
+        tags.push(format!(
+            "<meta {attribute}=\"{key}\" content=\"{}\" />",
+            escape_html(value)
+        ));
+    };
+
+    if !description.is_empty() {
+        meta("name", "description", description);
+    }
+    meta("property", "og:type", "article");
+    meta("property", "og:site_name", SITE_NAME);
+    if !title.is_empty() {
+        meta("property", "og:title", title);
+        meta("name", "twitter:title", title);
+    }
+    if !description.is_empty() {
+        meta("property", "og:description", description);
+        meta("name", "twitter:description", description);
+    }
+
+    let is_not_found = page_slug == not_found_page().slug;
+    let canonical_url = chrome.page_url.filter(|_| !is_not_found);
+    if let Some(url) = canonical_url {
+        meta("property", "og:url", url);
+    }
+
+    let image_url = rendered.image.as_deref().map(|image| match base_url {
+        Some(base) if image.starts_with('/') => format!("{base}{image}"),
+        _ => image.to_string(),
+    });
+    match &image_url {
+        Some(url) => {
+            meta("property", "og:image", url);
+            meta("name", "twitter:image", url);
+            meta("name", "twitter:card", "summary_large_image");
+        }
+        None => meta("name", "twitter:card", "summary"),
+    }
+
+    if let Some(modified_time) = &rendered.modified_time {
+        meta("property", "article:modified_time", modified_time);
+    }
+    if is_not_found {
+        meta("name", "robots", "noindex");
+    }
+    if let Some(url) = canonical_url {
+        tags.push(format!(
+            "<link rel=\"canonical\" href=\"{}\" />",
+            escape_html(url)
+        ));
+    }
+
+    tags.join("\n    ")
+}
+
+fn render_search_box_html(search: Option<SearchSource<'_>>) -> String {
+    let source_attribute = match search {
+        None => return String::new(),
+        Some(SearchSource::Endpoint(url)) => {
+            format!("data-search-endpoint=\"{}\"", escape_html(url))
+        }
+        Some(SearchSource::Index(url)) => format!("data-search-index=\"{}\"", escape_html(url)),
+    };
+    format!(
+        "<form class=\"search\" role=\"search\" {source_attribute}><input type=\"search\" name=\"q\" placeholder=\"Search docs\" aria-label=\"Search docs\" autocomplete=\"off\" /><div class=\"search-results\" hidden></div></form>"
+    )
+}
+
+/// A stand-in page for an old URL, for static hosts that cannot send real redirects.
+pub fn render_redirect_html(target_url: &str, target_title: &str) -> String {
+    let target_url = escape_html(target_url);
+    let target_title = escape_html(target_title);
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"utf-8\" />\n    <title>{target_title} - {SITE_NAME}</title>\n    <meta name=\"robots\" content=\"noindex\" />\n    <meta http-equiv=\"refresh\" content=\"0; url={target_url}\" />\n    <link rel=\"canonical\" href=\"{target_url}\" />\n  </head>\n  <body>\n    <p>This page has moved to <a href=\"{target_url}\">{target_title}</a>.</p>\n  </body>\n</html>\n"
+    )
+}
+
+pub fn render_fix_prompt_html(markdown_path: &str) -> String {
+    let edit_url = format!("{DOCS_REPO_BASE_URL}/edit/main/{markdown_path}");
+    let issue_url = format!("{DOCS_REPO_BASE_URL}/issues/new");
+
+    format!(
+        "<section class=\"fix-prompt\"><div class=\"callout tip\"><strong>Found a docs issue?</strong><div>Spotted outdated steps, typos, or missing details? Help improve this page for everyone.</div></div><div class=\"hero-actions\"><a class=\"btn btn-primary\" href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">Edit this page</a><a class=\"btn\" href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">Open an issue</a></div></section>",
+        escape_html(&edit_url),
+        escape_html(&issue_url)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        find_page, nav_sections,
+        render::{render_page, render_source},
+    };
+
+    #[test]
+    fn fills_every_template_placeholder() {
+        let page = find_page("guides/local-manga").expect("local manga guide");
+        let html = render_document(
+            page.slug,
+            Some(page.path),
+            &render_page(page),
+            nav_sections(),
+            &Chrome::default(),
+        );
+
+        assert!(!html.contains("{{"), "unfilled placeholder in page");
+        assert!(html.contains("<title>Local manga - Manatan Docs</title>"));
+        assert!(html.contains("/edit/main/docs/guides/local-manga.md"));
+        assert!(html.contains("class=\"item active\" href=\"/docs/guides/local-manga\""));
+        assert!(html.contains(
+            "<li><a href=\"/docs/guides\">Guides</a></li><li aria-current=\"page\">Local manga</li>"
+        ));
+        assert!(html.contains(
+            "<a class=\"next\" href=\"/docs/guides/local-anime\" rel=\"next\"><span>Next</span>Local anime</a>"
+        ));
+    }
+
+    #[test]
+    fn fills_head_meta_from_frontmatter() {
+        let rendered = render_source(
+            "---\ntitle: Kiku & Anki\ndescription: Set up \"Kiku\".\nimage: /assets/kiku.png\nlast_reviewed: 2025-01-10\n---\n\n# Kiku\n",
+        );
+        let chrome = Chrome {
+            base_url: Some("https://docs.example.com"),
+            page_url: Some("https://docs.example.com/docs/guides/kiku"),
+            ..Chrome::default()
+        };
+        let html = render_document("guides/kiku", None, &rendered, &[], &chrome);
+
+        assert!(
+            html.contains("<meta name=\"description\" content=\"Set up &quot;Kiku&quot;.\" />")
+        );
+        assert!(html.contains("<meta property=\"og:title\" content=\"Kiku &amp; Anki\" />"));
+        assert!(html.contains(
+            "<meta property=\"og:image\" content=\"https://docs.example.com/assets/kiku.png\" />"
+        ));
+        assert!(html.contains(
+            "<link rel=\"canonical\" href=\"https://docs.example.com/docs/guides/kiku\" />"
+        ));
+        assert!(
+            html.contains("<meta property=\"article:modified_time\" content=\"2025-01-10\" />")
+        );
+
+        let not_found = render_document("404", None, &rendered, &[], &chrome);
+        assert!(!not_found.contains("rel=\"canonical\""));
+        assert!(not_found.contains("content=\"noindex\""));
+    }
+
+    #[test]
+    fn fragments_carry_the_parts_the_template_places() {
+        let rendered = render_source("---\ntitle: Tips & tricks\n---\n\n## First\n\nBody.\n");
+        let parts = render_page_parts("guides/tips", None, &rendered, nav_sections(), "Preview");
+        let fragment = parts.to_fragment_html();
+
+        assert!(fragment.starts_with("<title>Tips &amp; tricks - Preview</title>"));
+        assert!(fragment.contains(&format!(
+            "<aside class=\"sidebar\">{}</aside>",
+            parts.sidebar_html
+        )));
+        assert!(fragment.contains(&format!(
+            "<article class=\"content\">{}</article>",
+            parts.content_html
+        )));
+        assert!(fragment.ends_with(&format!("<aside class=\"toc\">{}</aside>", parts.toc_html)));
+        assert!(parts.toc_html.contains("href=\"#first\""));
+
+        for (class, placeholder) in [
+            ("sidebar", "{{SIDEBAR}}"),
+            ("content", "{{CONTENT}}"),
+            ("toc", "{{TOC}}"),
+        ] {
+            assert!(
+                PAGE_TEMPLATE.contains(&format!("{class}\">{placeholder}<")),
+                "the template has no `{class}` element to patch"
+            );
+        }
+    }
+}
@@ -1,262 +1,413 @@
+pub mod check;
+pub mod discover;
+pub mod export;
+mod frontmatter;
+pub mod layout;
+pub mod nav;
+pub mod render;
+pub mod search;
+pub mod site;
+pub mod sitemap;
+mod suggest;
+
+pub use frontmatter::{Diagnostic, Frontmatter, Severity, Value, parse_toml_document};
+pub use suggest::{suggest_pages, suggest_slugs};
+
+use frontmatter::{Format, SplitError, split_source};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DocPage {
     pub slug: &'static str,
+    /// Markdown file the page was generated from, relative to the crate root.
+    pub path: &'static str,
     pub source: &'static str,
+    /// When the file was last committed (RFC 3339), if the crate was built from a git checkout.
+    pub last_modified: Option<&'static str>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A page read from disk at runtime instead of compiled in, so tools can work on another
+/// checkout. [`check::check_pages`] and [`export::export_site`] take these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcePage {
+    pub slug: String,
+    /// Relative to the docs repository root, like [`DocPage::path`].
+    pub path: String,
+    pub source: String,
+    pub last_modified: Option<String>,
+}
+
+impl From<&DocPage> for SourcePage {
+    fn from(page: &DocPage) -> Self {
+        SourcePage {
+            slug: page.slug.to_string(),
+            path: page.path.to_string(),
+            source: page.source.to_string(),
+            last_modified: page.last_modified.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NavItem {
-    pub slug: &'static str,
-    pub label: &'static str,
-    pub hint: &'static str,
+    pub slug: String,
+    pub label: String,
+    pub hint: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NavSection {
-    pub label: &'static str,
-    pub items: &'static [NavItem],
+    pub label: String,
+    pub items: Vec<NavItem>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParsedDoc<'a> {
-    pub title: &'a str,
-    pub description: &'a str,
+    pub frontmatter: Frontmatter,
     pub body: &'a str,
 }
 
-static DOC_ITEMS: [NavItem; 1] = [NavItem {
-    slug: "",
-    label: "Overview",
-    hint: "Start here",
-}];
-
-static GUIDE_ITEMS: [NavItem; 9] = [
-    NavItem {
-        slug: "guides",
-        label: "Guides home",
-        hint: "Browse guides",
-    },
-    NavItem {
-        slug: "guides/getting-started",
-        label: "Getting started",
-        hint: "Install + first steps",
-    },
-    NavItem {
-        slug: "guides/ankiconnect-guide",
-        label: "Ankiconnect setup",
-        hint: "Configure Anki settings",
-    },
-    NavItem {
-        slug: "guides/local-manga",
-        label: "Local manga",
-        hint: "Folder structure + formats",
-    },
-    NavItem {
-        slug: "guides/local-anime",
-        label: "Local anime",
-        hint: "Folder structure + formats",
-    },
-    NavItem {
-        slug: "guides/novels",
-        label: "Novels (EPUB)",
-        hint: "Import + reading",
-    },
-    NavItem {
-        slug: "guides/kiku-guide",
-        label: "Kiku flashcard setup",
-        hint: "Configure Kiku card type",
-    },
-    NavItem {
-        slug: "guides/jellyfin-setup",
-        label: "Jellyfin setup",
-        hint: "Configure Jellyfin for Manatan",
-    },
-    NavItem {
-        slug: "guides/troubleshooting",
-        label: "Troubleshooting",
-        hint: "Common issues",
-    },
-];
-
-static FAQ_ITEMS: [NavItem; 3] = [
-    NavItem {
-        slug: "faq",
-        label: "FAQ home",
-        hint: "Browse topics",
-    },
-    NavItem {
-        slug: "faq/general",
-        label: "General",
-        hint: "Downloads + privacy",
-    },
-    NavItem {
-        slug: "faq/local-files",
-        label: "Local files",
-        hint: "Not showing? ordering",
-    },
-];
-
-static NAV_SECTIONS: [NavSection; 3] = [
-    NavSection {
-        label: "Docs",
-        items: &DOC_ITEMS,
-    },
-    NavSection {
-        label: "Guides",
-        items: &GUIDE_ITEMS,
-    },
-    NavSection {
-        label: "FAQ",
-        items: &FAQ_ITEMS,
-    },
-];
-
-static PAGES: [DocPage; 13] = [
-    DocPage {
-        slug: "",
-        source: include_str!("../docs/index.md"),
-    },
-    DocPage {
-        slug: "guides",
-        source: include_str!("../docs/guides/index.md"),
-    },
-    DocPage {
-        slug: "guides/getting-started",
-        source: include_str!("../docs/guides/getting-started.md"),
-    },
-    DocPage {
-        slug: "guides/ankiconnect-guide",
-        source: include_str!("../docs/guides/ankiconnect-guide.md"),
-    },
-    DocPage {
-        slug: "guides/local-manga",
-        source: include_str!("../docs/guides/local-manga.md"),
-    },
-    DocPage {
-        slug: "guides/local-anime",
-        source: include_str!("../docs/guides/local-anime.md"),
-    },
-    DocPage {
-        slug: "guides/novels",
-        source: include_str!("../docs/guides/novels.md"),
-    },
-    DocPage {
-        slug: "guides/kiku-guide",
-        source: include_str!("../docs/guides/kiku-guide.md"),
-    },
-    DocPage {
-        slug: "guides/jellyfin-setup",
-        source: include_str!("../docs/guides/jellyfin-setup.md"),
-    },
-    DocPage {
-        slug: "guides/troubleshooting",
-        source: include_str!("../docs/guides/troubleshooting.md"),
-    },
-    DocPage {
-        slug: "faq",
-        source: include_str!("../docs/faq/index.md"),
-    },
-    DocPage {
-        slug: "faq/general",
-        source: include_str!("../docs/faq/general.md"),
-    },
-    DocPage {
-        slug: "faq/local-files",
-        source: include_str!("../docs/faq/local-files.md"),
-    },
-];
-
-static NOT_FOUND_PAGE: DocPage = DocPage {
-    slug: "404",
-    source: include_str!("../docs/404.md"),
-};
+const DEFAULT_TITLE: &str = "Manatan Docs";
+pub(crate) const DEFAULT_NAV_SECTION: &str = "Docs";
+
+static NAV_SECTIONS: OnceLock<Vec<NavSection>> = OnceLock::new();
+static ALIASES: OnceLock<HashMap<String, &'static DocPage>> = OnceLock::new();
+
+include!(concat!(env!("OUT_DIR"), "/pages.rs"));
 
 pub fn all_pages() -> &'static [DocPage] {
     &PAGES
 }
 
 pub fn nav_sections() -> &'static [NavSection] {
-    &NAV_SECTIONS
+    NAV_SECTIONS
+        .get_or_init(|| build_nav_sections(PAGES.iter().map(|page| (page.slug, page.source))))
 }
 
+/// Looks a page up by slug or by one of its `aliases`, which match in any spelling
+/// [`canonical_slug`] accepts. Callers can compare the returned page's slug with the one
+/// they asked for to tell when a redirect is due.
 pub fn find_page(slug: &str) -> Option<&'static DocPage> {
     let normalized = normalize_slug(slug);
-    PAGES.iter().find(|page| page.slug == normalized)
+    PAGES
+        .iter()
+        .find(|page| page.slug == normalized)
+        .or_else(|| page_aliases().get(&canonical_slug(slug)).copied())
+}
+
+/// Old slugs from `aliases`/`redirect_from` frontmatter, mapped to the page they now point to.
+pub fn page_aliases() -> &'static HashMap<String, &'static DocPage> {
+    ALIASES.get_or_init(|| {
+        build_aliases(PAGES.iter().map(|page| (page.slug, page.source)))
+            .into_iter()
+            .filter_map(|(alias, slug)| {
+                let page = PAGES.iter().find(|page| page.slug == slug)?;
+                Some((alias, page))
+            })
+            .collect()
+    })
+}
+
+/// Maps each alias in `(slug, source)` pairs to the slug of the page claiming it. An alias
+/// never shadows a real page; when two pages claim one, the first in order keeps it. Aliases
+/// failing [`is_valid_alias`] are skipped; `check` reports them.
+pub fn build_aliases<'a>(
+    pages: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> HashMap<String, String> {
+    let pages: Vec<(&str, &str)> = pages
+        .into_iter()
+        .map(|(slug, source)| (normalize_slug(slug), source))
+        .collect();
+    let mut aliases = HashMap::new();
+    for (slug, source) in &pages {
+        for alias in parse_doc(source).frontmatter.aliases {
+            if !is_valid_alias(&alias) {
+                continue;
+            }
+            let alias = canonical_slug(&alias);
+            if pages.iter().any(|(other, _)| *other == alias) {
+                continue;
+            }
+            aliases.entry(alias).or_insert_with(|| slug.to_string());
+        }
+    }
+    aliases
+}
+
+/// Whether an `aliases`/`redirect_from` entry is a plain docs path: at least one segment, no
+/// empty, `.` or `..` ones, and not under one of the export's [`export::RESERVED_OUTPUTS`].
+/// Anything else could name a place outside the docs or clash with the export's own files,
+/// since the export writes a redirect file at every alias.
+pub fn is_valid_alias(alias: &str) -> bool {
+    let alias = alias.trim().trim_matches('/');
+    let first_segment = alias.split('/').next().unwrap_or_default().to_lowercase();
+    !alias.is_empty()
+        && !alias.contains('\\')
+        && alias
+            .split('/')
+            .all(|segment| !matches!(segment, "" | "." | ".."))
+        && !export::RESERVED_OUTPUTS.contains(&first_segment.as_str())
+}
+
+/// The file under `assets_dir` that `/assets/<request_path>` names, or `None` when the path
+/// would reach outside it: `..` segments, absolute or drive paths, and symlinks pointing out.
+/// Dotfiles like `.DS_Store` are never served either. `request_path` is already
+/// percent-decoded.
+pub fn resolve_asset_path(assets_dir: &Path, request_path: &str) -> Option<PathBuf> {
+    if request_path.contains(['\\', '\0']) {
+        return None;
+    }
+
+    let mut path = assets_dir.to_path_buf();
+    for segment in request_path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+    {
+        let mut components = Path::new(segment).components();
+        let is_plain_name = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+        if !is_plain_name || segment.starts_with('.') {
+            return None;
+        }
+        path.push(segment);
+    }
+
+    let root = fs::canonicalize(assets_dir).ok()?;
+    let resolved = fs::canonicalize(&path).ok()?;
+    (resolved.starts_with(&root) && resolved.is_file()).then_some(resolved)
 }
 
 pub fn not_found_page() -> &'static DocPage {
     &NOT_FOUND_PAGE
 }
 
-pub fn parse_doc(source: &str) -> ParsedDoc<'_> {
-    let fallback_body = source.trim();
-    let Some(frontmatter_block) = source.strip_prefix("---\n") else {
-        return ParsedDoc {
-            title: "Manatan Docs",
-            description: "",
-            body: fallback_body,
-        };
-    };
+/// Builds the sidebar from `(slug, source)` pairs using each page's `nav_*` frontmatter.
+///
+/// Pages are ordered by `nav_order` (unset values sort last, then by slug) and sections
+/// appear in the order of their first page. A page without `nav_section` joins the section
+/// of its folder's index page, and `nav_label`/`nav_hint` fall back to `title`/`description`.
+pub fn build_nav_sections<'a>(
+    pages: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<NavSection> {
+    let mut entries: Vec<(&str, ParsedDoc<'_>)> = pages
+        .into_iter()
+        .map(|(slug, source)| (normalize_slug(slug), parse_doc(source)))
+        .collect();
+    entries.sort_by_key(|(slug, parsed)| {
+        let order = parsed.frontmatter.nav_order;
+        (order.is_none(), order, *slug)
+    });
 
-    let Some((frontmatter, body)) = frontmatter_block.split_once("\n---\n") else {
-        return ParsedDoc {
-            title: "Manatan Docs",
-            description: "",
-            body: fallback_body,
+    let mut sections: Vec<NavSection> = Vec::new();
+    for (slug, parsed) in &entries {
+        let frontmatter = &parsed.frontmatter;
+        let section_label = frontmatter
+            .nav_section
+            .as_deref()
+            .or_else(|| folder_nav_section(&entries, slug))
+            .unwrap_or(DEFAULT_NAV_SECTION);
+        let item = NavItem {
+            slug: slug.to_string(),
+            label: frontmatter
+                .nav_label
+                .as_deref()
+                .unwrap_or(parsed.title())
+                .to_string(),
+            hint: frontmatter
+                .nav_hint
+                .as_deref()
+                .unwrap_or(parsed.description())
+                .to_string(),
         };
-    };
-
-    let mut title = None;
-    let mut description = None;
 
-    for line in frontmatter.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
+        match sections
+            .iter_mut()
+            .find(|section| section.label == section_label)
+        {
+            Some(section) => section.items.push(item),
+            None => sections.push(NavSection {
+                label: section_label.to_string(),
+                items: vec![item],
+            }),
         }
+    }
 
-        let Some((raw_key, raw_value)) = trimmed.split_once(':') else {
-            continue;
-        };
+    sections
+}
 
-        let key = raw_key.trim();
-        let value = trim_wrapping_quotes(raw_value.trim());
+/// Splits a page into frontmatter and body. Accepts YAML between `---` lines or TOML between
+/// `+++` lines, with LF or CRLF line endings and an optional UTF-8 BOM. Pages without a
+/// readable block get empty frontmatter and the whole file as body.
+pub fn parse_doc(source: &str) -> ParsedDoc<'_> {
+    match split_source(source) {
+        Ok(split) => ParsedDoc {
+            frontmatter: split.parse().0,
+            body: split.body.trim_start(),
+        },
+        Err(_) => ParsedDoc {
+            frontmatter: Frontmatter::default(),
+            body: source.trim_start_matches('\u{feff}').trim(),
+        },
+    }
+}
 
-        match key {
-            "title" => title = Some(value),
-            "description" => description = Some(value),
-            _ => {}
+/// Parses a page like [`parse_doc`], reporting every problem found instead of quietly
+/// falling back. Warnings (a missing `description`, a mistyped `draft`) come back with the
+/// parsed page; any error fails the parse, with the warnings included. Diagnostics are
+/// sorted by position.
+pub fn parse_doc_strict(source: &str) -> Result<(ParsedDoc<'_>, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let split = match split_source(source) {
+        Ok(split) => split,
+        Err(SplitError::Missing) => {
+            return Err(vec![Diagnostic::error(
+                1,
+                1,
+                "missing frontmatter: the first line should be `---` (YAML) or `+++` (TOML)",
+            )]);
         }
+        Err(SplitError::Unclosed(format)) => {
+            let delimiter = match format {
+                Format::Yaml => "---",
+                Format::Toml => "+++",
+            };
+            return Err(vec![Diagnostic::error(
+                1,
+                1,
+                format!("frontmatter is never closed: add a `{delimiter}` line after the last key"),
+            )]);
+        }
+    };
+
+    let (frontmatter, mut diagnostics) = split.parse();
+    // Frontmatter that failed to parse is empty, so every field would read as missing.
+    let syntax_ok = !diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error);
+    if syntax_ok && frontmatter.title.is_none() {
+        diagnostics.push(Diagnostic::error(1, 1, "missing `title`"));
+    }
+    if syntax_ok && frontmatter.description.is_none() {
+        diagnostics.push(Diagnostic::warning(1, 1, "missing `description`"));
     }
 
-    ParsedDoc {
-        title: title.unwrap_or("Manatan Docs"),
-        description: description.unwrap_or(""),
-        body: body.trim_start(),
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Err(diagnostics);
+    }
+
+    let parsed = ParsedDoc {
+        frontmatter,
+        body: split.body.trim_start(),
+    };
+    Ok((parsed, diagnostics))
+}
+
+impl DocPage {
+    pub fn parse(&self) -> ParsedDoc<'static> {
+        parse_doc(self.source)
     }
 }
 
-fn normalize_slug(slug: &str) -> &str {
+impl ParsedDoc<'_> {
+    /// The page title, or a generic site title when the frontmatter has none.
+    pub fn title(&self) -> &str {
+        self.frontmatter.title.as_deref().unwrap_or(DEFAULT_TITLE)
+    }
+
+    pub fn description(&self) -> &str {
+        self.frontmatter.description.as_deref().unwrap_or_default()
+    }
+}
+
+/// The `nav_section` declared by the index page of the folder containing `slug`.
+fn folder_nav_section<'e>(entries: &'e [(&str, ParsedDoc<'_>)], slug: &str) -> Option<&'e str> {
+    let folder = slug.split('/').next().unwrap_or_default();
+    entries
+        .iter()
+        .find(|(entry_slug, _)| *entry_slug == folder)
+        .and_then(|(_, parsed)| parsed.frontmatter.nav_section.as_deref())
+}
+
+/// `slug` without surrounding whitespace and slashes.
+pub fn normalize_slug(slug: &str) -> &str {
     slug.trim().trim_matches('/')
 }
 
-fn trim_wrapping_quotes(value: &str) -> &str {
-    let stripped_double = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
-    if let Some(stripped) = stripped_double {
-        return stripped;
+/// The single spelling of a requested docs path: lowercase, without empty segments, a
+/// `.md`/`.html` extension or a trailing `index`. `Guides//Local-Manga.md` and
+/// `guides/local-manga/` both become `guides/local-manga`.
+pub fn canonical_slug(path: &str) -> String {
+    let lowercase = path.trim().to_lowercase();
+    let mut segments: Vec<&str> = lowercase
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if let Some(last) = segments.last_mut() {
+        *last = last
+            .strip_suffix(".md")
+            .or_else(|| last.strip_suffix(".html"))
+            .unwrap_or(last);
     }
+    if segments.last() == Some(&"index") {
+        segments.pop();
+    }
+    segments.join("/")
+}
 
-    let stripped_single = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\''));
-    if let Some(stripped) = stripped_single {
-        return stripped;
+/// Decodes `%XX` escapes in a URL path, so `/assets/my%20clip.mp4` names the file
+/// `my clip.mp4`. Malformed escapes are kept as written.
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
     }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
 
-    value
+/// Escapes every byte of a URL path segment except ASCII letters, digits and `-._~`, so any
+/// slug can go in a link or a `Location` header.
+pub(crate) fn percent_encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn finds_docs_home_page_with_empty_slug() {
@@ -264,12 +415,315 @@ mod tests {
         assert_eq!(page.slug, "");
     }
 
+    #[test]
+    fn discovers_pages_from_docs_directory() {
+        let page = find_page("/guides/local-manga/").expect("local manga guide should exist");
+        assert_eq!(page.path, "docs/guides/local-manga.md");
+
+        let guides_home = find_page("guides").expect("guides home page should exist");
+        assert_eq!(guides_home.path, "docs/guides/index.md");
+
+        assert!(find_page("404").is_none());
+        assert_eq!(not_found_page().path, "docs/404.md");
+    }
+
+    #[test]
+    fn canonicalises_requested_paths() {
+        for path in [
+            "guides/local-manga",
+            "/guides/local-manga/",
+            "//guides//local-manga",
+            "Guides/Local-Manga",
+            "guides/local-manga.md",
+            "guides/local-manga/index.html",
+        ] {
+            assert_eq!(canonical_slug(path), "guides/local-manga", "{path}");
+        }
+        assert_eq!(canonical_slug("index.md"), "");
+        assert_eq!(canonical_slug("/"), "");
+
+        for page in all_pages() {
+            assert_eq!(
+                canonical_slug(page.slug),
+                page.slug,
+                "{} is not canonical",
+                page.path
+            );
+        }
+    }
+
+    #[test]
+    fn escapes_and_unescapes_url_segments() {
+        assert_eq!(
+            percent_decode("%E3%83%9E%E3%83%B3%E3%82%AC.png"),
+            "マンガ.png"
+        );
+        assert_eq!(percent_decode("100%.png"), "100%.png");
+        assert_eq!(percent_decode("a%2.png"), "a%2.png");
+        assert_eq!(percent_decode("%+1.png"), "%+1.png");
+
+        assert_eq!(
+            percent_encode_segment("local-manga_2.0~"),
+            "local-manga_2.0~"
+        );
+        assert_eq!(percent_encode_segment("a?b c\n"), "a%3Fb%20c%0A");
+        assert_eq!(percent_encode_segment("マ"), "%E3%83%9E");
+        assert_eq!(
+            percent_decode(&percent_encode_segment("a%2F?#b")),
+            "a%2F?#b"
+        );
+    }
+
+    #[test]
+    fn resolves_aliases_to_their_page() {
+        let aliases = build_aliases([
+            (
+                "guides/anki",
+                "---\ntitle: Anki\naliases: [guides/ankiconnect-guide, /old-anki/]\nredirect_from: guides/local-manga\n---\n",
+            ),
+            ("guides/local-manga", "---\ntitle: Local manga\n---\n"),
+        ]);
+        assert_eq!(aliases["guides/ankiconnect-guide"], "guides/anki");
+        assert_eq!(aliases["old-anki"], "guides/anki");
+        assert!(!aliases.contains_key("guides/local-manga"));
+
+        let aliases = build_aliases([(
+            "guides/anki",
+            "---\ntitle: Anki\naliases: [../../x, guides/./anki-old, a//b, '..\\x', /, guides/old]\n---\n",
+        )]);
+        assert_eq!(aliases.keys().collect::<Vec<_>>(), ["guides/old"]);
+        assert!(is_valid_alias("/guides/old/"));
+        assert!(!is_valid_alias("guides/.."));
+        assert!(!is_valid_alias("assets/old.png"));
+        assert!(!is_valid_alias("/Sitemap.xml"));
+        assert!(!is_valid_alias("404.html"));
+    }
+
+    #[test]
+    fn keeps_requests_inside_the_assets_directory() {
+        let assets_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let asset = fs::read_dir(&assets_dir)
+            .expect("assets dir")
+            .filter_map(Result::ok)
+            .find(|entry| entry.path().is_dir())
+            .and_then(|dir| {
+                let file = fs::read_dir(dir.path())
+                    .ok()?
+                    .filter_map(Result::ok)
+                    .next()?;
+                Some(format!(
+                    "{}/{}",
+                    dir.file_name().to_string_lossy(),
+                    file.file_name().to_string_lossy()
+                ))
+            })
+            .expect("an asset in a subdirectory");
+
+        assert!(resolve_asset_path(&assets_dir, &asset).is_some());
+        assert!(resolve_asset_path(&assets_dir, &format!("/{asset}")).is_some());
+        for escape in [
+            "../Cargo.toml",
+            "a/../../Cargo.toml",
+            "./../Cargo.toml",
+            "..\\Cargo.toml",
+            "/etc/passwd",
+            "C:/Windows/win.ini",
+            ".gitkeep",
+            "",
+        ] {
+            assert_eq!(resolve_asset_path(&assets_dir, escape), None, "{escape}");
+        }
+    }
+
     #[test]
     fn parses_markdown_frontmatter() {
         let parsed = parse_doc("---\ntitle: Hello\ndescription: Hi there\n---\n\n# Body\n");
 
-        assert_eq!(parsed.title, "Hello");
-        assert_eq!(parsed.description, "Hi there");
+        assert_eq!(parsed.title(), "Hello");
+        assert_eq!(parsed.description(), "Hi there");
         assert_eq!(parsed.body, "# Body\n");
     }
+
+    #[test]
+    fn every_page_has_valid_frontmatter() {
+        for page in all_pages().iter().chain([not_found_page()]) {
+            let (Ok((_, diagnostics)) | Err(diagnostics)) = parse_doc_strict(page.source);
+            if !diagnostics.is_empty() {
+                let report: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+                panic!(
+                    "{} has frontmatter problems:\n{}",
+                    page.path,
+                    report.join("\n")
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn strict_parse_reports_positions() {
+        let (parsed, diagnostics) =
+            parse_doc_strict("---\ntitle: Hello\ndraft: maybe\nweight: 3\n---\n")
+                .expect("warnings do not fail the parse");
+        assert_eq!(parsed.title(), "Hello");
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.severity))
+            .collect();
+        assert_eq!(
+            found,
+            [(1, 1, Severity::Warning), (3, 1, Severity::Warning)]
+        );
+
+        let diagnostics = parse_doc_strict("---\ntitle: Hello\noops\ndraft: true\n---\n")
+            .expect_err("frontmatter is not valid YAML");
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.severity))
+            .collect();
+        assert_eq!(found, [(3, 1, Severity::Error)]);
+
+        let diagnostics = parse_doc_strict("---\ntitle: \"Hello\ndraft: true\n---\n")
+            .expect_err("the quote is never closed");
+        assert_eq!(
+            (
+                diagnostics.len(),
+                diagnostics[0].line,
+                diagnostics[0].column
+            ),
+            (1, 2, 8)
+        );
+
+        let missing = parse_doc_strict("# No frontmatter\n").expect_err("frontmatter is missing");
+        assert_eq!(missing[0].severity, Severity::Error);
+        assert!(parse_doc("# No frontmatter\n").frontmatter.title.is_none());
+    }
+
+    #[test]
+    fn builds_nav_sections_from_frontmatter() {
+        let sections = build_nav_sections([
+            (
+                "guides/b",
+                "---\ntitle: B page\ndescription: About B\nnav_order: 2\n---\n",
+            ),
+            (
+                "guides",
+                "---\ntitle: Guides\nnav_section: Guides\nnav_label: Guides home\nnav_order: 1\n---\n",
+            ),
+            (
+                "guides/a",
+                "---\ntitle: A page\nnav_hint: Read first\nnav_order: 1\n---\n",
+            ),
+            ("", "---\ntitle: Home\nnav_order: 0\n---\n"),
+        ]);
+
+        let labels: Vec<_> = sections
+            .iter()
+            .map(|section| section.label.as_str())
+            .collect();
+        assert_eq!(labels, ["Docs", "Guides"]);
+
+        let guides: Vec<_> = sections[1]
+            .items
+            .iter()
+            .map(|item| (item.slug.as_str(), item.label.as_str(), item.hint.as_str()))
+            .collect();
+        assert_eq!(
+            guides,
+            [
+                ("guides", "Guides home", ""),
+                ("guides/a", "A page", "Read first"),
+                ("guides/b", "B page", "About B"),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_page_appears_in_nav() {
+        let nav_slugs: Vec<_> = nav_sections()
+            .iter()
+            .flat_map(|section| &section.items)
+            .map(|item| item.slug.as_str())
+            .collect();
+
+        for page in all_pages() {
+            assert!(
+                nav_slugs.contains(&page.slug),
+                "{} is missing from nav",
+                page.slug
+            );
+        }
+    }
+
+    #[test]
+    fn parses_crlf_bom_and_toml_frontmatter() {
+        let crlf = parse_doc("\u{feff}---  \r\ntitle: Hello\r\n---\t\r\n\r\n# Body\r\n");
+        assert_eq!(crlf.title(), "Hello");
+        assert_eq!(crlf.body, "# Body\r\n");
+
+        let toml = parse_doc("+++\ntitle = \"Hello\"\ntags = [\"a\", \"b\"]\n+++\n# Body\n");
+        assert_eq!(toml.title(), "Hello");
+        assert_eq!(toml.frontmatter.tags, ["a", "b"]);
+        assert_eq!(toml.body, "# Body\n");
+
+        let empty = parse_doc("---\n---\n# Body");
+        assert_eq!(empty.frontmatter, Frontmatter::default());
+        assert_eq!(empty.body, "# Body");
+    }
+
+    fn yaml_doc(title: &str, tags: &[String], body: &str) -> String {
+        format!(
+            "---\ntitle: \"{title}\"\ndescription: \"About {title}\"\ntags:\n{}\n---\n{body}",
+            tags.iter()
+                .map(|tag| format!("  - {tag}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+
+    fn toml_doc(title: &str, tags: &[String], body: &str) -> String {
+        let tags: Vec<_> = tags.iter().map(|tag| format!("\"{tag}\"")).collect();
+        format!(
+            "+++\ntitle = \"{title}\"\ndescription = \"About {title}\"\ntags = [{}]\n+++\n{body}",
+            tags.join(", ")
+        )
+    }
+
+    /// Windows-style variant of a document: BOM, CRLF endings and padded delimiter lines.
+    fn windows_variant(source: &str, padding: &str) -> String {
+        let padded: Vec<_> = source
+            .split('\n')
+            .map(|line| match line {
+                "---" | "+++" => format!("{line}{padding}"),
+                other => other.to_string(),
+            })
+            .collect();
+        format!("\u{feff}{}", padded.join("\r\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn equivalent_documents_parse_identically(
+            title in "[A-Za-z0-9][A-Za-z0-9 ()?!.,-]{0,30}",
+            tags in prop::collection::vec("[a-z][a-z0-9-]{0,8}", 1..4),
+            body in "[A-Za-z0-9 #*>.-]{0,40}(\n[A-Za-z0-9 #*>.-]{0,40}){0,4}",
+            padding in "[ \t]{0,3}",
+        ) {
+            let yaml = yaml_doc(&title, &tags, &body);
+            let expected = parse_doc(&yaml);
+            prop_assert_eq!(expected.title(), title.as_str());
+            prop_assert_eq!(&expected.frontmatter.tags, &tags);
+
+            for variant in [
+                windows_variant(&yaml, &padding),
+                toml_doc(&title, &tags, &body),
+                windows_variant(&toml_doc(&title, &tags, &body), &padding),
+            ] {
+                let parsed = parse_doc(&variant);
+                prop_assert_eq!(&parsed.frontmatter, &expected.frontmatter);
+                prop_assert_eq!(parsed.body.replace("\r\n", "\n"), expected.body);
+                let strict = parse_doc_strict(&variant).map(|(parsed, _)| parsed.frontmatter);
+                prop_assert_eq!(strict.as_ref(), Ok(&expected.frontmatter));
+            }
+        }
+    }
 }
@@ -0,0 +1,113 @@
+use crate::{DEFAULT_NAV_SECTION, NavItem, NavSection, normalize_slug};
+
+/// One step of the trail above a page, like "Guides" in Docs › Guides › Local manga.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breadcrumb {
+    pub label: String,
+    pub slug: String,
+}
+
+/// The pages before and after one page in sidebar order, across section boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdjacentPages<'a> {
+    pub previous: Option<&'a NavItem>,
+    pub next: Option<&'a NavItem>,
+}
+
+/// Docs home, then the page's section (linking to its first page, usually the folder's index),
+/// then the page itself. Empty for pages missing from the sidebar, like the 404 page.
+pub fn breadcrumbs(sections: &[NavSection], slug: &str) -> Vec<Breadcrumb> {
+    let slug = normalize_slug(slug);
+    let Some((section, item)) = sections.iter().find_map(|section| {
+        let item = section.items.iter().find(|item| item.slug == slug)?;
+        Some((section, item))
+    }) else {
+        return Vec::new();
+    };
+
+    let home_label = sections
+        .iter()
+        .find(|section| section.items.iter().any(|item| item.slug.is_empty()))
+        .map_or(DEFAULT_NAV_SECTION, |section| section.label.as_str());
+    let mut trail = vec![Breadcrumb {
+        label: home_label.to_string(),
+        slug: String::new(),
+    }];
+
+    if let Some(first) = section.items.first()
+        && !first.slug.is_empty()
+    {
+        trail.push(Breadcrumb {
+            label: section.label.clone(),
+            slug: first.slug.clone(),
+        });
+    }
+    if trail.last().is_some_and(|crumb| crumb.slug != item.slug) {
+        trail.push(Breadcrumb {
+            label: item.label.clone(),
+            slug: item.slug.clone(),
+        });
+    }
+
+    trail
+}
+
+pub fn adjacent_pages<'a>(sections: &'a [NavSection], slug: &str) -> AdjacentPages<'a> {
+    let slug = normalize_slug(slug);
+    let items: Vec<&NavItem> = sections.iter().flat_map(|section| &section.items).collect();
+    let Some(index) = items.iter().position(|item| item.slug == slug) else {
+        return AdjacentPages {
+            previous: None,
+            next: None,
+        };
+    };
+
+    AdjacentPages {
+        previous: index.checked_sub(1).map(|previous| items[previous]),
+        next: items.get(index + 1).copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nav_sections;
+
+    fn labels(trail: &[Breadcrumb]) -> Vec<&str> {
+        trail.iter().map(|crumb| crumb.label.as_str()).collect()
+    }
+
+    #[test]
+    fn builds_breadcrumbs_from_sections() {
+        let sections = nav_sections();
+        assert_eq!(
+            labels(&breadcrumbs(sections, "guides/local-manga")),
+            ["Docs", "Guides", "Local manga"]
+        );
+        assert_eq!(labels(&breadcrumbs(sections, "guides")), ["Docs", "Guides"]);
+        assert_eq!(labels(&breadcrumbs(sections, "")), ["Docs"]);
+        assert!(breadcrumbs(sections, "404").is_empty());
+        assert_eq!(breadcrumbs(sections, "faq/general")[1].slug, "faq");
+    }
+
+    #[test]
+    fn links_neighbours_across_sections() {
+        let sections = nav_sections();
+        let adjacent = adjacent_pages(sections, "guides/ankiconnect-guide");
+        assert_eq!(
+            adjacent.previous.map(|item| item.slug.as_str()),
+            Some("guides/getting-started")
+        );
+        assert_eq!(
+            adjacent.next.map(|item| item.slug.as_str()),
+            Some("guides/local-manga")
+        );
+
+        assert!(adjacent_pages(sections, "").previous.is_none());
+        let faq_home = adjacent_pages(sections, "faq");
+        assert_eq!(
+            faq_home.previous.map(|item| item.slug.as_str()),
+            Some("guides/troubleshooting")
+        );
+    }
+}
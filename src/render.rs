@@ -0,0 +1,438 @@
+mod callouts;
+mod highlight;
+mod tree;
+
+pub use callouts::CalloutKind;
+pub use highlight::highlight;
+pub use tree::{EntryKind, TREE_FENCE, TreeNode, page_trees, parse_tree, render_tree_html};
+
+use std::collections::HashSet;
+
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd, html};
+
+use self::{
+    callouts::convert_callouts, highlight::highlight_code_blocks, tree::convert_tree_fences,
+};
+use crate::{
+    DocPage, NavSection,
+    nav::{adjacent_pages, breadcrumbs},
+    normalize_slug, parse_doc,
+};
+
+/// A page rendered to HTML, ready to drop into a site template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedPage {
+    pub title: String,
+    pub description: String,
+    /// `image` frontmatter, for social previews.
+    pub image: Option<String>,
+    /// `last_reviewed` frontmatter, falling back to the file's last commit date.
+    pub modified_time: Option<String>,
+    pub body_html: String,
+    pub headings: Vec<Heading>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// 1 for `#`, 2 for `##`, and so on.
+    pub level: u8,
+    pub text: String,
+    /// The `id` attribute on the rendered heading, for `#fragment` links.
+    pub id: String,
+}
+
+/// A heading with the lower-level headings that follow it, up to the next heading of the
+/// same or a higher level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadingNode {
+    pub heading: Heading,
+    pub children: Vec<HeadingNode>,
+}
+
+/// Heading levels listed in the per-page table of contents.
+const TOC_LEVELS: std::ops::RangeInclusive<u8> = 2..=3;
+
+impl RenderedPage {
+    pub fn heading_tree(&self) -> Vec<HeadingNode> {
+        heading_tree(&self.headings)
+    }
+}
+
+pub fn render_page(page: &DocPage) -> RenderedPage {
+    let mut rendered = render_source(page.source);
+    if rendered.modified_time.is_none() {
+        rendered.modified_time = page.last_modified.map(str::to_string);
+    }
+    rendered
+}
+
+/// Renders a page from its Markdown source, frontmatter included. The preview uses this
+/// directly so it can render files as they change on disk.
+pub fn render_source(source: &str) -> RenderedPage {
+    let parsed = parse_doc(source);
+    let mut events = transform_events(Parser::new_ext(parsed.body, markdown_options()).collect());
+    let headings = collect_headings(&events);
+    assign_heading_ids(&mut events, &headings);
+
+    RenderedPage {
+        title: parsed.title().to_string(),
+        description: parsed.description().to_string(),
+        image: parsed.frontmatter.image.clone(),
+        modified_time: parsed.frontmatter.last_reviewed.clone(),
+        headings,
+        body_html: events_to_html(events),
+    }
+}
+
+pub fn markdown_to_html(markdown: &str) -> String {
+    events_to_html(transform_events(
+        Parser::new_ext(markdown, markdown_options()).collect(),
+    ))
+}
+
+/// The pulldown-cmark extensions every docs page is rendered with.
+pub fn markdown_options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options
+}
+
+/// The docs-specific rewrites applied on top of plain CommonMark: callouts, tree fences and
+/// syntax highlighting.
+fn transform_events(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    highlight_code_blocks(convert_tree_fences(convert_callouts(events)))
+}
+
+fn events_to_html<'a>(events: impl IntoIterator<Item = Event<'a>>) -> String {
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events.into_iter());
+    html_output
+}
+
+fn collect_headings(events: &[Event<'_>]) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<Heading> = None;
+    let mut used_ids = HashSet::new();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                current = Some(Heading {
+                    level: heading_level_number(*level),
+                    text: String::new(),
+                    id: id.as_deref().unwrap_or_default().to_string(),
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut heading) = current.take() {
+                    if heading.id.is_empty() {
+                        heading.id = unique_slug(heading_slug(&heading.text), &mut used_ids);
+                    } else {
+                        used_ids.insert(heading.id.clone());
+                    }
+                    headings.push(heading);
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = current.as_mut() {
+                    heading.text.push_str(text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+fn assign_heading_ids(events: &mut [Event<'_>], headings: &[Heading]) {
+    let mut ids = headings.iter().map(|heading| heading.id.clone());
+    for event in events {
+        if let Event::Start(Tag::Heading { id, .. }) = event
+            && let Some(heading_id) = ids.next()
+        {
+            *id = Some(CowStr::from(heading_id));
+        }
+    }
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation and symbols dropped, spaces
+/// turned into hyphens. "1) Download and install" becomes `1-download-and-install`.
+pub fn heading_slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Appends `-1`, `-2`, ... to repeated slugs the way GitHub does.
+fn unique_slug(base: String, used: &mut HashSet<String>) -> String {
+    let mut candidate = base.clone();
+    let mut suffix = 0;
+    while used.contains(&candidate) {
+        suffix += 1;
+        candidate = format!("{base}-{suffix}");
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+pub fn heading_tree(headings: &[Heading]) -> Vec<HeadingNode> {
+    let mut roots: Vec<HeadingNode> = Vec::new();
+    for heading in headings {
+        insert_heading(&mut roots, heading.clone());
+    }
+    roots
+}
+
+fn insert_heading(siblings: &mut Vec<HeadingNode>, heading: Heading) {
+    match siblings.last_mut() {
+        Some(last) if last.heading.level < heading.level => {
+            insert_heading(&mut last.children, heading)
+        }
+        _ => siblings.push(HeadingNode {
+            heading,
+            children: Vec::new(),
+        }),
+    }
+}
+
+fn heading_level_number(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+pub fn render_sidebar_html(sections: &[NavSection], active_slug: &str) -> String {
+    let mut html = String::new();
+
+    for section in sections {
+        html.push_str(&format!(
+            "<div class=\"section\">{}</div>",
+            escape_html(&section.label)
+        ));
+
+        for item in &section.items {
+            let active_class = if item.slug == active_slug {
+                " active"
+            } else {
+                ""
+            };
+            html.push_str(&format!(
+                "<a class=\"item{}\" href=\"{}\">{}<span>{}</span></a>",
+                active_class,
+                docs_path_for_slug(&item.slug),
+                escape_html(&item.label),
+                escape_html(&item.hint),
+            ));
+        }
+    }
+
+    html
+}
+
+/// "Did you mean" links for the 404 page to `(slug, source)` pairs, from
+/// [`crate::suggest_pages`] or [`crate::suggest_slugs`].
+pub fn render_suggestions_html(pages: &[(&str, &str)]) -> String {
+    if pages.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from("<section class=\"suggestions\"><h2>Did you mean</h2><ul>");
+    for (slug, source) in pages {
+        let parsed = parse_doc(source);
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            docs_path_for_slug(slug),
+            escape_html(parsed.title())
+        ));
+        if !parsed.description().is_empty() {
+            html.push_str(&format!(" - {}", escape_html(parsed.description())));
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul></section>");
+    html
+}
+
+/// "Docs › Guides › Local manga" above the page content. Empty for pages with no trail.
+pub fn render_breadcrumbs_html(sections: &[NavSection], slug: &str) -> String {
+    let trail = breadcrumbs(sections, slug);
+    let Some((current, parents)) = trail
+        .split_last()
+        .filter(|(_, parents)| !parents.is_empty())
+    else {
+        return String::new();
+    };
+
+    let mut html = String::from("<nav class=\"breadcrumbs\" aria-label=\"Breadcrumb\"><ol>");
+    for crumb in parents {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>",
+            docs_path_for_slug(&crumb.slug),
+            escape_html(&crumb.label)
+        ));
+    }
+    html.push_str(&format!(
+        "<li aria-current=\"page\">{}</li></ol></nav>",
+        escape_html(&current.label)
+    ));
+    html
+}
+
+/// Previous and next links at the bottom of a page, in sidebar order.
+pub fn render_adjacent_pages_html(sections: &[NavSection], slug: &str) -> String {
+    let adjacent = adjacent_pages(sections, slug);
+    if adjacent.previous.is_none() && adjacent.next.is_none() {
+        return String::new();
+    }
+
+    let mut html =
+        String::from("<nav class=\"page-links\" aria-label=\"Previous and next pages\">");
+    for (class, direction, item) in [
+        ("previous", "Previous", adjacent.previous),
+        ("next", "Next", adjacent.next),
+    ] {
+        if let Some(item) = item {
+            html.push_str(&format!(
+                "<a class=\"{class}\" href=\"{}\" rel=\"{}\"><span>{direction}</span>{}</a>",
+                docs_path_for_slug(&item.slug),
+                if class == "previous" { "prev" } else { "next" },
+                escape_html(&item.label)
+            ));
+        }
+    }
+    html.push_str("</nav>");
+    html
+}
+
+/// The "On this page" panel: links to the page's `##` and `###` headings.
+pub fn render_toc_html(headings: &[Heading]) -> String {
+    let listed: Vec<Heading> = headings
+        .iter()
+        .filter(|heading| TOC_LEVELS.contains(&heading.level))
+        .cloned()
+        .collect();
+    if listed.is_empty() {
+        return "<div class=\"label\">Explore</div><a href=\"/docs\">Docs overview<div class=\"small\">Start here</div></a>".to_string();
+    }
+
+    let mut html = String::new();
+    html.push_str("<div class=\"label\">On this page</div><nav aria-label=\"On this page\">");
+    push_toc_list(&mut html, &heading_tree(&listed));
+    html.push_str("</nav>");
+    html
+}
+
+fn push_toc_list(html: &mut String, nodes: &[HeadingNode]) {
+    html.push_str("<ul>");
+    for node in nodes {
+        html.push_str(&format!(
+            "<li><a class=\"toc-level-{}\" href=\"#{}\">{}</a>",
+            node.heading.level,
+            escape_html(&node.heading.id),
+            escape_html(&node.heading.text),
+        ));
+        if !node.children.is_empty() {
+            push_toc_list(html, &node.children);
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+}
+
+pub fn docs_path_for_slug(slug: &str) -> String {
+    let normalized = normalize_slug(slug);
+    if normalized.is_empty() {
+        "/docs".to_string()
+    } else {
+        format!("/docs/{normalized}")
+    }
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_page_with_headings() {
+        let rendered = render_source(
+            "---\ntitle: Local manga\ndescription: Folders\n---\n\n# Local manga\n\n## The `cover.jpg` file\n\nText.\n",
+        );
+
+        assert_eq!(rendered.title, "Local manga");
+        assert_eq!(rendered.description, "Folders");
+        assert!(
+            rendered
+                .body_html
+                .contains("<h1 id=\"local-manga\">Local manga</h1>")
+        );
+        assert_eq!(
+            rendered.headings,
+            [
+                Heading {
+                    level: 1,
+                    text: "Local manga".to_string(),
+                    id: "local-manga".to_string(),
+                },
+                Heading {
+                    level: 2,
+                    text: "The cover.jpg file".to_string(),
+                    id: "the-coverjpg-file".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_github_style_anchors_and_heading_tree() {
+        assert_eq!(
+            heading_slug("1) Download and install"),
+            "1-download-and-install"
+        );
+        assert_eq!(
+            heading_slug("Chapters are in the wrong order"),
+            "chapters-are-in-the-wrong-order"
+        );
+        assert_eq!(heading_slug("Novels (EPUB)"), "novels-epub");
+
+        let rendered = render_source("# Guide\n## Tips\n### Stable ordering\n## Tips\n### Tips\n");
+        let ids: Vec<_> = rendered.headings.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["guide", "tips", "stable-ordering", "tips-1", "tips-2"]
+        );
+
+        let tree = rendered.heading_tree();
+        assert_eq!(tree.len(), 1);
+        let sections: Vec<_> = tree[0]
+            .children
+            .iter()
+            .map(|node| (node.heading.id.as_str(), node.children.len()))
+            .collect();
+        assert_eq!(sections, [("tips", 1), ("tips-1", 1)]);
+    }
+}
@@ -0,0 +1,254 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use super::escape_html;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Warning,
+    Danger,
+}
+
+impl CalloutKind {
+    /// The GitHub alert marker name, as in `> [!TIP]`. `IMPORTANT` and `CAUTION` are
+    /// accepted as aliases of note and danger.
+    fn from_marker(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "NOTE" | "IMPORTANT" => Some(CalloutKind::Note),
+            "TIP" => Some(CalloutKind::Tip),
+            "WARNING" => Some(CalloutKind::Warning),
+            "DANGER" | "CAUTION" => Some(CalloutKind::Danger),
+            _ => None,
+        }
+    }
+
+    /// The kind a legacy bold label such as "Tip" or "Security note" names: a kind name,
+    /// optionally after one qualifying word. Longer labels like "Note that" are just bold text.
+    fn from_label(label: &str) -> Option<Self> {
+        match label.split_whitespace().collect::<Vec<_>>()[..] {
+            [name] => Self::from_marker(name),
+            [qualifier, name] if qualifier.chars().all(char::is_alphabetic) => {
+                Self::from_marker(name)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn class_name(self) -> &'static str {
+        match self {
+            CalloutKind::Note => "note",
+            CalloutKind::Tip => "tip",
+            CalloutKind::Warning => "warning",
+            CalloutKind::Danger => "danger",
+        }
+    }
+
+    fn default_title(self) -> &'static str {
+        match self {
+            CalloutKind::Note => "Note",
+            CalloutKind::Tip => "Tip",
+            CalloutKind::Warning => "Warning",
+            CalloutKind::Danger => "Danger",
+        }
+    }
+}
+
+/// Turns blockquotes that open with a GitHub alert marker (`> [!TIP]`) or a bold label
+/// (`> **Tip:**`, `> ⚠️ **WARNING**`) into `<div class="callout tip">` blocks. Other
+/// blockquotes are left alone.
+pub(super) fn convert_callouts(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut open_quotes: Vec<bool> = Vec::new();
+    let mut index = 0;
+
+    while index < events.len() {
+        match &events[index] {
+            Event::Start(Tag::BlockQuote(_)) => {
+                let callout = detect_callout(&events[index + 1..]);
+                open_quotes.push(callout.is_some());
+                match callout {
+                    Some(callout) => {
+                        output.push(Event::Html(CowStr::from(format!(
+                            "<div class=\"callout {}\" role=\"note\"><strong>{}</strong>\n",
+                            callout.kind.class_name(),
+                            escape_html(&callout.title),
+                        ))));
+                        output.extend(callout.first_events);
+                        index += 1 + callout.consumed;
+                        continue;
+                    }
+                    None => output.push(events[index].clone()),
+                }
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                if open_quotes.pop() == Some(true) {
+                    output.push(Event::Html(CowStr::from("</div>\n")));
+                } else {
+                    output.push(events[index].clone());
+                }
+            }
+            event => output.push(event.clone()),
+        }
+        index += 1;
+    }
+
+    output
+}
+
+struct Callout<'a> {
+    kind: CalloutKind,
+    title: String,
+    /// What is left of the first paragraph once the marker or label is removed.
+    first_events: Vec<Event<'a>>,
+    /// How many events after the blockquote start `first_events` replaces.
+    consumed: usize,
+}
+
+/// Looks at the first paragraph of a blockquote, given the events after its start.
+fn detect_callout<'a>(events: &[Event<'a>]) -> Option<Callout<'a>> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+    let paragraph_len = events
+        .iter()
+        .position(|event| matches!(event, Event::End(TagEnd::Paragraph)))?;
+    let inner = &events[1..paragraph_len];
+
+    let (kind, title, rest) = alert_marker(inner).or_else(|| bold_label(inner))?;
+
+    let rest = trim_leading_break(rest);
+    let first_events = if rest.is_empty() {
+        Vec::new()
+    } else {
+        let mut paragraph = vec![Event::Start(Tag::Paragraph)];
+        paragraph.extend(rest);
+        paragraph.push(Event::End(TagEnd::Paragraph));
+        paragraph
+    };
+
+    Some(Callout {
+        kind,
+        title,
+        first_events,
+        consumed: paragraph_len + 1,
+    })
+}
+
+/// `[!KIND]` at the start of the paragraph. The parser splits it over several text events
+/// (it looks like a link reference), so the leading text is merged before matching.
+fn alert_marker<'a>(inner: &[Event<'a>]) -> Option<(CalloutKind, String, Vec<Event<'a>>)> {
+    let text_len = inner
+        .iter()
+        .position(|event| !matches!(event, Event::Text(_)))
+        .unwrap_or(inner.len());
+    let merged: String = inner[..text_len]
+        .iter()
+        .filter_map(|event| match event {
+            Event::Text(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect();
+
+    let marker = merged.trim_start().strip_prefix("[!")?;
+    let (name, after) = marker.split_once(']')?;
+    let kind = CalloutKind::from_marker(name)?;
+
+    let mut rest = Vec::new();
+    let after = after.trim_start();
+    if !after.is_empty() {
+        rest.push(Event::Text(CowStr::from(after.to_string())));
+    }
+    rest.extend(inner[text_len..].iter().cloned());
+    Some((kind, kind.default_title().to_string(), rest))
+}
+
+/// A bold label ending in a colon opens the paragraph, optionally after an emoji:
+/// `**Tip:** text` or `**Tip**: text`.
+fn bold_label<'a>(inner: &[Event<'a>]) -> Option<(CalloutKind, String, Vec<Event<'a>>)> {
+    let mut start = 0;
+    if let Some(Event::Text(text)) = inner.first()
+        && !text.chars().any(char::is_alphanumeric)
+    {
+        start = 1;
+    }
+
+    let [
+        Event::Start(Tag::Strong),
+        Event::Text(label),
+        Event::End(TagEnd::Strong),
+        after @ ..,
+    ] = &inner[start..]
+    else {
+        return None;
+    };
+
+    let label = label.trim();
+    let colon_after = matches!(after.first(), Some(Event::Text(text)) if text.starts_with(':'));
+    if !label.ends_with(':') && !colon_after {
+        return None;
+    }
+    let title = label.trim_end_matches(':').trim_end();
+    let kind = CalloutKind::from_label(title)?;
+
+    let mut rest: Vec<Event<'a>> = after.to_vec();
+    if let Some(Event::Text(text)) = rest.first() {
+        let trimmed = text.trim_start_matches(':').trim_start().to_string();
+        if trimmed.is_empty() {
+            rest.remove(0);
+        } else {
+            rest[0] = Event::Text(CowStr::from(trimmed));
+        }
+    }
+    Some((kind, title.to_string(), rest))
+}
+
+fn trim_leading_break(mut events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    while matches!(events.first(), Some(Event::SoftBreak | Event::HardBreak)) {
+        events.remove(0);
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::markdown_to_html;
+
+    #[test]
+    fn renders_alerts_and_legacy_labels_as_callouts() {
+        assert_eq!(
+            markdown_to_html("> [!WARNING]\n> Anki 25.09 or later is required.\n"),
+            "<div class=\"callout warning\" role=\"note\"><strong>Warning</strong>\n<p>Anki 25.09 or later is required.</p>\n</div>\n"
+        );
+        assert_eq!(
+            markdown_to_html("> **Security note:** only install what you trust.\n"),
+            "<div class=\"callout note\" role=\"note\"><strong>Security note</strong>\n<p>only install what you trust.</p>\n</div>\n"
+        );
+        assert_eq!(
+            markdown_to_html("> ⚠️ **WARNING:**\n>\n> Not covered here.\n"),
+            "<div class=\"callout warning\" role=\"note\"><strong>WARNING</strong>\n<p>Not covered here.</p>\n</div>\n"
+        );
+        assert!(markdown_to_html("> [!DANGER]\n> Deletes data.\n").contains("callout danger"));
+        assert_eq!(
+            markdown_to_html("> Just a quote with **bold**.\n"),
+            "<blockquote>\n<p>Just a quote with <strong>bold</strong>.</p>\n</blockquote>\n"
+        );
+        assert!(markdown_to_html("> **Tip**: colon outside.\n").contains("callout tip"));
+    }
+
+    #[test]
+    fn leaves_bold_text_that_only_mentions_a_kind_alone() {
+        for quote in [
+            "> **Note that** the app restarts.\n",
+            "> **Warning signs:** the reader stutters.\n",
+            "> **Notebook:** a place for notes.\n",
+            "> **Not a tip:** just bold.\n",
+            "> **Tip** without a colon.\n",
+        ] {
+            assert!(
+                markdown_to_html(quote).starts_with("<blockquote>"),
+                "{quote}"
+            );
+        }
+    }
+}
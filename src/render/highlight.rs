@@ -0,0 +1,445 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+
+use super::escape_html;
+
+/// The languages [`highlight`] understands, after resolving aliases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Json,
+    Shell,
+    PowerShell,
+    Toml,
+    Yaml,
+}
+
+impl Language {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" | "jsonc" | "json5" => Some(Language::Json),
+            "bash" | "sh" | "shell" | "zsh" | "console" | "shell-session" => Some(Language::Shell),
+            "powershell" | "ps1" | "ps" | "pwsh" => Some(Language::PowerShell),
+            "toml" => Some(Language::Toml),
+            "yaml" | "yml" => Some(Language::Yaml),
+            _ => None,
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Language::Json => &["true", "false", "null"],
+            Language::Shell => &[
+                "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case",
+                "esac", "in", "function", "return", "export", "local",
+            ],
+            Language::PowerShell => &[
+                "if", "else", "elseif", "foreach", "for", "while", "do", "switch", "function",
+                "return", "param", "try", "catch", "finally", "in",
+            ],
+            Language::Toml => &["true", "false"],
+            Language::Yaml => &["true", "false", "null", "yes", "no", "on", "off", "~"],
+        }
+    }
+
+    fn has_hash_comments(self) -> bool {
+        self != Language::Json
+    }
+
+    /// `//` and `/* */` comments, which JSONC and JSON5 allow. Plain JSON has none to misread.
+    fn has_slash_comments(self) -> bool {
+        self == Language::Json
+    }
+
+    fn has_variables(self) -> bool {
+        matches!(self, Language::Shell | Language::PowerShell)
+    }
+
+    fn is_shell_like(self) -> bool {
+        matches!(self, Language::Shell | Language::PowerShell)
+    }
+}
+
+/// Token classes, emitted as `hl-*` CSS classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Property,
+    Variable,
+    Function,
+    Parameter,
+    Prompt,
+}
+
+impl Class {
+    fn css(self) -> &'static str {
+        match self {
+            Class::Comment => "hl-comment",
+            Class::String => "hl-string",
+            Class::Number => "hl-number",
+            Class::Keyword => "hl-keyword",
+            Class::Property => "hl-property",
+            Class::Variable => "hl-variable",
+            Class::Function => "hl-function",
+            Class::Parameter => "hl-parameter",
+            Class::Prompt => "hl-prompt",
+        }
+    }
+}
+
+/// Highlights `code` as `language`, returning escaped HTML with `<span class="hl-*">`
+/// tokens, or `None` when the language is not supported.
+pub fn highlight(language: &str, code: &str) -> Option<String> {
+    let language = Language::from_name(language)?;
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut in_block_comment = false;
+    for line in code.split_inclusive('\n') {
+        highlight_line(language, line, &mut in_block_comment, &mut html);
+    }
+    Some(html)
+}
+
+fn push_token(html: &mut String, class: Option<Class>, text: &str) {
+    match class {
+        Some(class) => {
+            html.push_str(&format!(
+                "<span class=\"{}\">{}</span>",
+                class.css(),
+                escape_html(text)
+            ));
+        }
+        None => html.push_str(&escape_html(text)),
+    }
+}
+
+/// Highlights one line. `in_block_comment` carries an unclosed `/* */` over to the next line.
+fn highlight_line(language: Language, line: &str, in_block_comment: &mut bool, html: &mut String) {
+    let mut rest = line;
+
+    if *in_block_comment {
+        let end = match rest.find("*/") {
+            Some(index) => {
+                *in_block_comment = false;
+                index + 2
+            }
+            None => rest.trim_end_matches('\n').len(),
+        };
+        push_token(html, Some(Class::Comment), &rest[..end]);
+        rest = &rest[end..];
+    }
+
+    let indent = rest.len() - rest.trim_start().len();
+    push_token(html, None, &rest[..indent]);
+    rest = &rest[indent..];
+
+    if language == Language::Shell
+        && let Some(after) = rest.strip_prefix("$ ")
+    {
+        push_token(html, Some(Class::Prompt), "$ ");
+        rest = after;
+    }
+    if language == Language::PowerShell
+        && rest.starts_with("PS ")
+        && let Some(end) = rest.find("> ")
+    {
+        push_token(html, Some(Class::Prompt), &rest[..end + 2]);
+        rest = &rest[end + 2..];
+    }
+    if language == Language::Toml && rest.starts_with('[') {
+        push_token(html, Some(Class::Property), rest.trim_end());
+        push_token(html, None, &rest[rest.trim_end().len()..]);
+        return;
+    }
+    if language == Language::Yaml
+        && let Some(after) = rest.strip_prefix("- ")
+    {
+        push_token(html, None, "- ");
+        rest = after;
+    }
+
+    let mut expect_command = language.is_shell_like();
+    let mut at_line_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            let end = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            push_token(html, None, &rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        if c == '#' && language.has_hash_comments() {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            push_token(html, Some(Class::Comment), &rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        if c == '/' && language.has_slash_comments() {
+            let end = if rest.starts_with("//") {
+                Some(rest.find('\n').unwrap_or(rest.len()))
+            } else {
+                rest.strip_prefix("/*")
+                    .map(|comment| match comment.find("*/") {
+                        Some(index) => index + 4,
+                        None => {
+                            *in_block_comment = true;
+                            rest.trim_end_matches('\n').len()
+                        }
+                    })
+            };
+            if let Some(end) = end {
+                push_token(html, Some(Class::Comment), &rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+        }
+
+        if c == '"' || c == '\'' {
+            let end = string_end(rest, c);
+            let is_key = at_line_start && is_key_separator(language, &rest[end..]);
+            let class = if is_key
+                || (language == Language::Json && rest[end..].trim_start().starts_with(':'))
+            {
+                Class::Property
+            } else {
+                Class::String
+            };
+            push_token(html, Some(class), &rest[..end]);
+            rest = &rest[end..];
+            at_line_start = false;
+            expect_command = false;
+            continue;
+        }
+
+        if c == '$' && language.has_variables() {
+            let end = variable_end(rest);
+            if end > 1 {
+                push_token(html, Some(Class::Variable), &rest[..end]);
+                rest = &rest[end..];
+                at_line_start = false;
+                expect_command = false;
+                continue;
+            }
+        }
+
+        if language.is_shell_like() && matches!(c, '|' | ';' | '&' | '(' | '{') {
+            push_token(html, None, &rest[..1]);
+            rest = &rest[1..];
+            expect_command = true;
+            continue;
+        }
+
+        let end = word_end(language, rest);
+        if end == 0 {
+            push_token(html, None, &rest[..c.len_utf8()]);
+            rest = &rest[c.len_utf8()..];
+            at_line_start = false;
+            continue;
+        }
+
+        let word = &rest[..end];
+        let class = classify_word(language, word, at_line_start, expect_command, &rest[end..]);
+        push_token(html, class, word);
+        rest = &rest[end..];
+        at_line_start = false;
+        if class != Some(Class::Keyword) {
+            expect_command = false;
+        }
+    }
+}
+
+fn classify_word(
+    language: Language,
+    word: &str,
+    at_line_start: bool,
+    expect_command: bool,
+    after: &str,
+) -> Option<Class> {
+    let lower = word.to_ascii_lowercase();
+    if language.keywords().contains(&lower.as_str()) {
+        return Some(Class::Keyword);
+    }
+    if at_line_start && is_key_separator(language, after) {
+        return Some(Class::Property);
+    }
+    if is_number(word) {
+        return Some(Class::Number);
+    }
+    if language.is_shell_like() {
+        if word.starts_with('-') && word.len() > 1 {
+            return Some(Class::Parameter);
+        }
+        if expect_command {
+            return Some(Class::Function);
+        }
+        if language == Language::PowerShell && is_cmdlet(word) {
+            return Some(Class::Function);
+        }
+    }
+    None
+}
+
+/// Whether the text after a word makes it a `key =` (TOML) or `key:` (YAML) key.
+fn is_key_separator(language: Language, after: &str) -> bool {
+    let after = after.trim_start_matches([' ', '\t']);
+    match language {
+        Language::Toml => after.starts_with('='),
+        Language::Yaml => after.starts_with(':'),
+        _ => false,
+    }
+}
+
+/// `Get-ChildItem`-style Verb-Noun names.
+fn is_cmdlet(word: &str) -> bool {
+    let Some((verb, noun)) = word.split_once('-') else {
+        return false;
+    };
+    [verb, noun].iter().all(|part| {
+        part.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+            && part.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+fn is_number(word: &str) -> bool {
+    let digits = word.strip_prefix('-').unwrap_or(word);
+    digits.chars().next().is_some_and(|c| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | '_' | 'e' | 'E' | '+' | '-'))
+        && digits.matches('.').count() <= 1
+}
+
+/// Byte length of the string literal at the start of `text`, closing quote included. An
+/// unterminated string runs to the end of the line.
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '\n' => return index,
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return index + c.len_utf8(),
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+/// `$name`, `${name}`, `$env:NAME` or the special `$?`/`$1`.
+fn variable_end(text: &str) -> usize {
+    let after = &text[1..];
+    if after.starts_with('{') {
+        return after.find('}').map_or(text.len(), |end| end + 2);
+    }
+    if after.starts_with(['?', '#', '@', '*', '!']) {
+        return 2;
+    }
+    1 + after
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(after.len())
+}
+
+fn word_end(language: Language, text: &str) -> usize {
+    let is_word_char = |c: char| match language {
+        // Shell words run until whitespace or an operator: paths and URLs stay whole.
+        Language::Shell | Language::PowerShell => {
+            !c.is_whitespace()
+                && !matches!(
+                    c,
+                    '|' | ';' | '&' | '(' | ')' | '{' | '}' | '"' | '\'' | '$' | '<' | '>'
+                )
+        }
+        _ => c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+' | '~'),
+    };
+    text.find(|c: char| !is_word_char(c)).unwrap_or(text.len())
+}
+
+/// Replaces fenced code blocks in a supported language with highlighted HTML, in the same
+/// `<pre><code class="language-*">` shape pulldown-cmark renders.
+pub(super) fn highlight_code_blocks(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut current: Option<(String, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info)))
+                if Language::from_name(fence_language(info)).is_some() =>
+            {
+                current = Some((fence_language(info).to_string(), String::new()));
+            }
+            Event::Text(ref text) if current.is_some() => {
+                if let Some((_, code)) = current.as_mut() {
+                    code.push_str(text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if current.is_some() => {
+                let (language, code) = current.take().unwrap_or_default();
+                let body = highlight(&language, &code).unwrap_or_else(|| escape_html(&code));
+                output.push(Event::Html(CowStr::from(format!(
+                    "<pre><code class=\"language-{}\">{body}</code></pre>\n",
+                    escape_html(&language)
+                ))));
+            }
+            event => output.push(event),
+        }
+    }
+
+    output
+}
+
+fn fence_language(info: &str) -> &str {
+    info.split_whitespace().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::markdown_to_html;
+
+    #[test]
+    fn highlights_json_and_shell() {
+        assert_eq!(
+            highlight("json", "{\"port\": 4568, \"debug\": false}").unwrap(),
+            "{<span class=\"hl-property\">&quot;port&quot;</span>: <span class=\"hl-number\">4568</span>, <span class=\"hl-property\">&quot;debug&quot;</span>: <span class=\"hl-keyword\">false</span>}"
+        );
+        assert_eq!(
+            highlight("console", "$ cargo run --bin preview-docs # start\n").unwrap(),
+            "<span class=\"hl-prompt\">$ </span><span class=\"hl-function\">cargo</span> run <span class=\"hl-parameter\">--bin</span> preview-docs <span class=\"hl-comment\"># start</span>\n"
+        );
+        assert_eq!(
+            highlight("powershell", "Get-ChildItem $env:APPDATA\\Manatan").unwrap(),
+            "<span class=\"hl-function\">Get-ChildItem</span> <span class=\"hl-variable\">$env:APPDATA</span>\\Manatan"
+        );
+    }
+
+    #[test]
+    fn highlights_comments_in_jsonc() {
+        assert_eq!(
+            highlight("jsonc", "{ // port\n  /* a\n  b */ \"port\": 1 /* c */\n}").unwrap(),
+            "{ <span class=\"hl-comment\">// port</span>\n  <span class=\"hl-comment\">/* a</span>\n<span class=\"hl-comment\">  b */</span> <span class=\"hl-property\">&quot;port&quot;</span>: <span class=\"hl-number\">1</span> <span class=\"hl-comment\">/* c */</span>\n}"
+        );
+        assert_eq!(
+            highlight("json", "{\"url\": \"http://a/*b\"}").unwrap(),
+            "{<span class=\"hl-property\">&quot;url&quot;</span>: <span class=\"hl-string\">&quot;http://a/*b&quot;</span>}"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_languages_as_plain_code() {
+        assert_eq!(
+            markdown_to_html("```text\nhttp://127.0.0.1:4568/ <b>\n```\n"),
+            "<pre><code class=\"language-text\">http://127.0.0.1:4568/ &lt;b&gt;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn highlights_toml_code_blocks() {
+        assert_eq!(
+            markdown_to_html("```toml\n[server]\nport = 4568 # default\n```\n"),
+            "<pre><code class=\"language-toml\"><span class=\"hl-property\">[server]</span>\n<span class=\"hl-property\">port</span> = <span class=\"hl-number\">4568</span> <span class=\"hl-comment\"># default</span>\n</code></pre>\n"
+        );
+    }
+}
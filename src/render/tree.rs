@@ -0,0 +1,308 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Parser, Tag, TagEnd};
+
+use super::{escape_html, markdown_options};
+use crate::parse_doc;
+
+/// Code fence language that renders as a folder tree.
+pub const TREE_FENCE: &str = "tree";
+
+/// One folder or file in a ```` ```tree ```` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub name: String,
+    pub kind: EntryKind,
+    /// Written in brackets, like `[Local Manga folder]`: stands for a path the reader picks.
+    pub placeholder: bool,
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Folder,
+    Image,
+    Archive,
+    Video,
+    Book,
+    File,
+}
+
+impl EntryKind {
+    /// Folders end in `/` (or are drawn with 📁); files are typed by extension.
+    fn detect(name: &str, folder_marker: bool) -> Self {
+        if folder_marker || name.ends_with('/') {
+            return EntryKind::Folder;
+        }
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "avif" | "bmp" => EntryKind::Image,
+            "zip" | "cbz" | "rar" | "cbr" | "7z" | "cb7" | "tar" => EntryKind::Archive,
+            "mp4" | "mkv" | "webm" | "avi" | "mov" => EntryKind::Video,
+            "epub" | "pdf" => EntryKind::Book,
+            _ => EntryKind::File,
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            EntryKind::Folder => "📁",
+            EntryKind::Image => "🖼️",
+            EntryKind::Archive => "📦",
+            EntryKind::Video => "🎥",
+            EntryKind::Book => "📖",
+            EntryKind::File => "📄",
+        }
+    }
+
+    /// Spoken in place of the icon.
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryKind::Folder => "folder",
+            EntryKind::Image => "image",
+            EntryKind::Archive => "archive",
+            EntryKind::Video => "video",
+            EntryKind::Book => "book",
+            EntryKind::File => "file",
+        }
+    }
+}
+
+/// Parses the body of a ```` ```tree ```` block. Nesting follows indentation:
+///
+/// ```text
+/// [Local Manga folder]/
+///   My Series Title/
+///     cover.jpg
+///     001.cbz
+/// ```
+///
+/// Box-drawing layouts (`├─ 📁 001`) are accepted too; the drawing characters and icons are
+/// dropped and only the column where each name starts matters.
+pub fn parse_tree(text: &str) -> Vec<TreeNode> {
+    // (column the name starts at, node), for the current path from the root.
+    let mut stack: Vec<(usize, TreeNode)> = Vec::new();
+    let mut roots = Vec::new();
+
+    for line in text.lines() {
+        let Some((column, node)) = parse_line(line) else {
+            continue;
+        };
+        while let Some((open_column, _)) = stack.last()
+            && *open_column >= column
+        {
+            close_node(&mut stack, &mut roots);
+        }
+        stack.push((column, node));
+    }
+    while !stack.is_empty() {
+        close_node(&mut stack, &mut roots);
+    }
+
+    roots
+}
+
+fn close_node(stack: &mut Vec<(usize, TreeNode)>, roots: &mut Vec<TreeNode>) {
+    let Some((_, mut node)) = stack.pop() else {
+        return;
+    };
+    if !node.children.is_empty() {
+        node.kind = EntryKind::Folder;
+    }
+    match stack.last_mut() {
+        Some((_, parent)) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+fn parse_line(line: &str) -> Option<(usize, TreeNode)> {
+    let mut column = 0;
+    let mut folder_marker = false;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '📁' || c == '📂' {
+            folder_marker = true;
+        } else if !is_tree_decoration(c) {
+            break;
+        }
+        // Variation selectors take no space on screen.
+        if c != '\u{FE0F}' {
+            column += 1;
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    let name = rest.trim_end();
+    if name.is_empty() {
+        return None;
+    }
+
+    let kind = EntryKind::detect(name, folder_marker);
+    let name = name.strip_suffix('/').unwrap_or(name);
+    let (name, placeholder) = match name.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+        Some(inner) => (inner, true),
+        None => (name, false),
+    };
+
+    Some((
+        column,
+        TreeNode {
+            name: name.to_string(),
+            kind,
+            placeholder,
+            children: Vec::new(),
+        },
+    ))
+}
+
+/// Whitespace, box-drawing characters and the icons the old text layouts used.
+fn is_tree_decoration(c: char) -> bool {
+    c.is_whitespace()
+        || ('\u{2500}'..='\u{257F}').contains(&c)
+        || matches!(c, '🖼' | '📦' | '🎥' | '🎬' | '📖' | '📄' | '\u{FE0F}')
+}
+
+/// Nested lists with an icon per entry. The icon carries the entry kind for screen readers.
+pub fn render_tree_html(nodes: &[TreeNode]) -> String {
+    let mut html =
+        String::from("<div class=\"file-tree\" role=\"group\" aria-label=\"Folder structure\">");
+    push_tree_list(&mut html, nodes);
+    html.push_str("</div>\n");
+    html
+}
+
+fn push_tree_list(html: &mut String, nodes: &[TreeNode]) {
+    html.push_str("<ul>");
+    for node in nodes {
+        let name = if node.placeholder {
+            format!("<em>{}</em>", escape_html(&node.name))
+        } else {
+            escape_html(&node.name)
+        };
+        html.push_str(&format!(
+            "<li class=\"{}\"><span class=\"tree-icon\" role=\"img\" aria-label=\"{}\">{}</span> {name}",
+            node.kind.label(),
+            node.kind.label(),
+            node.kind.icon(),
+        ));
+        if !node.children.is_empty() {
+            push_tree_list(html, &node.children);
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+}
+
+/// Every ```` ```tree ```` block in a page's Markdown source, in order.
+pub fn page_trees(source: &str) -> Vec<Vec<TreeNode>> {
+    let mut trees = Vec::new();
+    let mut current: Option<String> = None;
+
+    for event in Parser::new_ext(parse_doc(source).body, markdown_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(language)))
+                if is_tree_fence(&language) =>
+            {
+                current = Some(String::new());
+            }
+            Event::Text(text) => {
+                if let Some(body) = current.as_mut() {
+                    body.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(body) = current.take() {
+                    trees.push(parse_tree(&body));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    trees
+}
+
+/// Replaces ```` ```tree ```` code blocks with rendered trees.
+pub(super) fn convert_tree_fences(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut current: Option<String> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref language)))
+                if is_tree_fence(language) =>
+            {
+                current = Some(String::new());
+            }
+            Event::Text(ref text) if current.is_some() => {
+                if let Some(body) = current.as_mut() {
+                    body.push_str(text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if current.is_some() => {
+                let body = current.take().unwrap_or_default();
+                output.push(Event::Html(CowStr::from(render_tree_html(&parse_tree(
+                    &body,
+                )))));
+            }
+            event => output.push(event),
+        }
+    }
+
+    output
+}
+
+fn is_tree_fence(info: &str) -> bool {
+    info.split_whitespace().next() == Some(TREE_FENCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(nodes: &[TreeNode]) -> Vec<(String, EntryKind, usize)> {
+        nodes
+            .iter()
+            .map(|node| (node.name.clone(), node.kind, node.children.len()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_indented_and_box_drawing_layouts_alike() {
+        let indented = parse_tree(
+            "[Local Manga folder]/\n  My Series Title/\n    cover.jpg\n    001/\n      001.jpg\n    002.cbz\n",
+        );
+        let drawn = parse_tree(
+            "📁 [Local Manga folder]\n├─ 📁 My Series Title\n│   ├─ 🖼️ cover.jpg\n│   ├─ 📁 001\n│   │   └─ 🖼️ 001.jpg\n│   └─ 📦 002.cbz\n",
+        );
+        assert_eq!(indented, drawn);
+
+        let root = &indented[0];
+        assert!(root.placeholder);
+        assert_eq!(root.name, "Local Manga folder");
+        assert_eq!(
+            names(&root.children[0].children),
+            [
+                ("cover.jpg".to_string(), EntryKind::Image, 0),
+                ("001".to_string(), EntryKind::Folder, 1),
+                ("002.cbz".to_string(), EntryKind::Archive, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_tree_fences_as_nested_lists() {
+        let html = crate::render::markdown_to_html("```tree\nShow/\n  ep01.mkv\n```\n");
+        assert_eq!(
+            html,
+            "<div class=\"file-tree\" role=\"group\" aria-label=\"Folder structure\"><ul><li class=\"folder\"><span class=\"tree-icon\" role=\"img\" aria-label=\"folder\">📁</span> Show<ul><li class=\"video\"><span class=\"tree-icon\" role=\"img\" aria-label=\"video\">🎥</span> ep01.mkv</li></ul></li></ul></div>\n"
+        );
+
+        let page = crate::find_page("guides/local-anime").expect("local anime guide");
+        let trees = page_trees(page.source);
+        assert_eq!(trees.len(), 2);
+        assert_eq!(trees[1][0].children[0].children.len(), 2);
+    }
+}
@@ -0,0 +1,527 @@
+mod tokenize;
+
+pub use tokenize::{normalize, tokenize};
+
+use tokenize::{is_single_cjk, normalize_with_offsets};
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+use crate::{
+    PAGES, normalize_slug, parse_doc,
+    render::{docs_path_for_slug, markdown_options, render_source},
+};
+
+/// Results returned by [`SearchIndex::search`] when no limit is given.
+pub const DEFAULT_RESULT_LIMIT: usize = 20;
+
+/// At most this many sections from one page are returned, so one long page cannot crowd out
+/// the rest.
+const RESULTS_PER_PAGE: usize = 3;
+const SNIPPET_CHARS: usize = 160;
+
+const TITLE_WEIGHT: u32 = 8;
+const HEADING_WEIGHT: u32 = 6;
+const DESCRIPTION_WEIGHT: u32 = 3;
+/// Repeats of a word in a section's text stop counting after this many.
+const MAX_TEXT_HITS: u32 = 4;
+
+/// Searchable text for every docs page, split into one entry per heading so results can
+/// link straight to the matching section.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    entries: Vec<SearchEntry>,
+}
+
+/// One section of a page: the text under a heading, or the page intro before its first `##`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchEntry {
+    pub slug: String,
+    pub page_title: String,
+    /// `description` frontmatter; only set on the page intro.
+    pub description: String,
+    /// `None` for the page intro.
+    pub heading: Option<String>,
+    /// Heading `id` on the rendered page.
+    pub anchor: Option<String>,
+    pub text: String,
+    terms: EntryTerms,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct EntryTerms {
+    title: HashSet<String>,
+    heading: HashSet<String>,
+    description: HashSet<String>,
+    text: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub slug: String,
+    pub page_title: String,
+    pub heading: Option<String>,
+    /// `/docs/...` URL, with a `#fragment` for sections.
+    pub url: String,
+    pub snippet: String,
+    pub score: u32,
+}
+
+static SEARCH_INDEX: OnceLock<SearchIndex> = OnceLock::new();
+
+/// The index over every page in `all_pages()`.
+pub fn search_index() -> &'static SearchIndex {
+    SEARCH_INDEX.get_or_init(|| SearchIndex::build(PAGES.iter().map(|p| (p.slug, p.source))))
+}
+
+impl SearchIndex {
+    /// Builds an index from `(slug, source)` pairs.
+    pub fn build<'a>(pages: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let entries = pages
+            .into_iter()
+            .flat_map(|(slug, source)| page_entries(normalize_slug(slug), source))
+            .collect();
+        SearchIndex { entries }
+    }
+
+    pub fn entries(&self) -> &[SearchEntry] {
+        &self.entries
+    }
+
+    /// Ranks sections containing every token of `query` (see [`tokenize`]). The last token
+    /// also matches as a prefix, so results update sensibly while someone is still typing,
+    /// and a lone kana or kanji matches anywhere in a bigram.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scored: Vec<(u32, &SearchEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| score_entry(entry, &terms).map(|score| (score, entry)))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.slug.cmp(&b.slug))
+                .then_with(|| a.anchor.cmp(&b.anchor))
+        });
+
+        let mut per_page: HashMap<&str, usize> = HashMap::new();
+        scored
+            .into_iter()
+            .filter(|(_, entry)| {
+                let count = per_page.entry(&entry.slug).or_default();
+                *count += 1;
+                *count <= RESULTS_PER_PAGE
+            })
+            .take(limit)
+            .map(|(score, entry)| SearchResult {
+                slug: entry.slug.clone(),
+                page_title: entry.page_title.clone(),
+                heading: entry.heading.clone(),
+                url: entry.url(),
+                snippet: snippet(entry, &terms),
+                score,
+            })
+            .collect()
+    }
+
+    /// Serialises the index for client-side search. `map_url` turns each entry's `/docs/...`
+    /// URL into whatever the consumer links to. Entries carry their tokenized terms and the
+    /// file carries the ranking constants, so the browser ranks exactly like [`Self::search`]
+    /// without a second copy of the weights.
+    pub fn to_json(&self, map_url: impl Fn(&str) -> String) -> String {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{{\"url\":{},\"slug\":{},\"anchor\":{},\"title\":{},\"heading\":{},\
+                     \"description\":{},\"text\":{},\"terms\":{}}}",
+                    json_string(&map_url(&entry.url())),
+                    json_string(&entry.slug),
+                    json_optional_string(entry.anchor.as_deref()),
+                    json_string(&entry.page_title),
+                    json_optional_string(entry.heading.as_deref()),
+                    json_string(&entry.description),
+                    json_string(&entry.text),
+                    entry.terms.to_json(),
+                )
+            })
+            .collect();
+        format!(
+            "{{\"ranking\":{{\"titleWeight\":{TITLE_WEIGHT},\"headingWeight\":{HEADING_WEIGHT},\
+             \"descriptionWeight\":{DESCRIPTION_WEIGHT},\"maxTextHits\":{MAX_TEXT_HITS},\
+             \"resultsPerPage\":{RESULTS_PER_PAGE},\"resultLimit\":{DEFAULT_RESULT_LIMIT},\
+             \"snippetChars\":{SNIPPET_CHARS}}},\"entries\":[{}]}}",
+            entries.join(",")
+        )
+    }
+}
+
+impl EntryTerms {
+    /// Sorted, so the exported index is the same from one build to the next.
+    fn to_json(&self) -> String {
+        let list = |terms: &HashSet<String>| {
+            let mut terms: Vec<&String> = terms.iter().collect();
+            terms.sort();
+            let terms: Vec<String> = terms.into_iter().map(|term| json_string(term)).collect();
+            format!("[{}]", terms.join(","))
+        };
+        let mut text: Vec<(&String, &u32)> = self.text.iter().collect();
+        text.sort();
+        let text: Vec<String> = text
+            .into_iter()
+            .map(|(term, count)| format!("{}:{count}", json_string(term)))
+            .collect();
+        format!(
+            "{{\"title\":{},\"heading\":{},\"description\":{},\"text\":{{{}}}}}",
+            list(&self.title),
+            list(&self.heading),
+            list(&self.description),
+            text.join(",")
+        )
+    }
+}
+
+impl SearchEntry {
+    pub fn url(&self) -> String {
+        let path = docs_path_for_slug(&self.slug);
+        match &self.anchor {
+            Some(anchor) => format!("{path}#{anchor}"),
+            None => path,
+        }
+    }
+}
+
+pub fn results_to_json(query: &str, results: &[SearchResult]) -> String {
+    let results: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "{{\"url\":{},\"title\":{},\"heading\":{},\"snippet\":{},\"score\":{}}}",
+                json_string(&result.url),
+                json_string(&result.page_title),
+                json_optional_string(result.heading.as_deref()),
+                json_string(&result.snippet),
+                result.score,
+            )
+        })
+        .collect();
+    format!(
+        "{{\"query\":{},\"results\":[{}]}}",
+        json_string(query),
+        results.join(",")
+    )
+}
+
+fn page_entries(slug: &str, source: &str) -> Vec<SearchEntry> {
+    let parsed = parse_doc(source);
+    let page_title = parsed.title().to_string();
+    let rendered = render_source(source);
+    // Headings come out of the parser in the same order the renderer assigned ids.
+    let mut heading_ids = rendered.headings.iter().map(|heading| heading.id.clone());
+
+    let mut entries = vec![SearchEntry {
+        slug: slug.to_string(),
+        page_title: page_title.clone(),
+        description: parsed.description().to_string(),
+        heading: None,
+        anchor: None,
+        text: String::new(),
+        terms: EntryTerms::default(),
+    }];
+    let mut in_heading = None;
+
+    for event in Parser::new_ext(parsed.body, markdown_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let anchor = heading_ids.next();
+                if level > HeadingLevel::H1 {
+                    entries.push(SearchEntry {
+                        slug: slug.to_string(),
+                        page_title: page_title.clone(),
+                        description: String::new(),
+                        heading: Some(String::new()),
+                        anchor,
+                        text: String::new(),
+                        terms: EntryTerms::default(),
+                    });
+                }
+                in_heading = Some(level);
+            }
+            Event::End(TagEnd::Heading(_)) => in_heading = None,
+            Event::Text(text) | Event::Code(text) => {
+                let Some(entry) = entries.last_mut() else {
+                    continue;
+                };
+                match (in_heading, entry.heading.as_mut()) {
+                    (Some(HeadingLevel::H1), _) => {}
+                    (Some(_), Some(heading)) => heading.push_str(&text),
+                    _ => entry.text.push_str(&text),
+                }
+            }
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::TableCell) => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for entry in &mut entries {
+        entry.text = entry.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        entry.terms = EntryTerms {
+            title: tokenize(&entry.page_title).into_iter().collect(),
+            heading: tokenize(entry.heading.as_deref().unwrap_or_default())
+                .into_iter()
+                .collect(),
+            description: tokenize(&entry.description).into_iter().collect(),
+            text: tokenize(&entry.text)
+                .into_iter()
+                .fold(HashMap::new(), |mut counts, term| {
+                    *counts.entry(term).or_default() += 1;
+                    counts
+                }),
+        };
+    }
+
+    entries
+}
+
+/// `None` unless every query term appears in the page title or in the section itself.
+fn score_entry(entry: &SearchEntry, terms: &[String]) -> Option<u32> {
+    let mut total = 0;
+    for (index, term) in terms.iter().enumerate() {
+        let allow_prefix = index == terms.len() - 1;
+        let single_cjk = is_single_cjk(term);
+        let matches = |candidate: &String| {
+            candidate == term
+                || (allow_prefix && candidate.starts_with(term.as_str()))
+                || (single_cjk && candidate.contains(term.as_str()))
+        };
+
+        let in_title = entry.terms.title.iter().any(matches);
+        let mut score = 0;
+        // The page title matches on every section; only the intro is rewarded for it.
+        if in_title && entry.heading.is_none() {
+            score += TITLE_WEIGHT;
+        }
+        if entry.terms.heading.iter().any(matches) {
+            score += HEADING_WEIGHT;
+        }
+        if entry.terms.description.iter().any(matches) {
+            score += DESCRIPTION_WEIGHT;
+        }
+        let text_hits: u32 = entry
+            .terms
+            .text
+            .iter()
+            .filter(|(candidate, _)| matches(candidate))
+            .map(|(_, count)| *count)
+            .sum();
+        score += text_hits.min(MAX_TEXT_HITS);
+
+        if score == 0 && !in_title {
+            return None;
+        }
+        total += score;
+    }
+    (total > 0).then_some(total)
+}
+
+/// Up to [`SNIPPET_CHARS`] of the section text, starting at the sentence with the first
+/// matching word.
+fn snippet(entry: &SearchEntry, terms: &[String]) -> String {
+    let source = if entry.text.is_empty() {
+        &entry.description
+    } else {
+        &entry.text
+    };
+    let start = first_match(source, terms)
+        .map(|index| source[..index].rfind(". ").map_or(0, |dot| dot + 2))
+        .unwrap_or(0);
+
+    let rest = &source[start..];
+    let mut snippet: String = rest.chars().take(SNIPPET_CHARS).collect();
+    if rest.chars().count() > SNIPPET_CHARS {
+        snippet = format!("{}…", snippet.trim_end());
+    }
+    if start > 0 {
+        snippet = format!("…{snippet}");
+    }
+    snippet
+}
+
+/// Byte offset in `source` of the character where the earliest term match starts. Terms are
+/// matched against the normalised text, then mapped back to the source character that
+/// produced the match.
+fn first_match(source: &str, terms: &[String]) -> Option<usize> {
+    let (normalized, offsets) = normalize_with_offsets(source);
+    let index = terms
+        .iter()
+        .filter_map(|term| normalized.find(term.as_str()))
+        .min()?;
+    let piece = offsets.partition_point(|(normalized_offset, _)| *normalized_offset <= index);
+    Some(offsets[piece.checked_sub(1)?].1)
+}
+
+fn json_optional_string(value: Option<&str>) -> String {
+    value.map_or("null".to_string(), json_string)
+}
+
+/// `value` as a JSON string literal, which is also safe to paste into a `<script>`.
+pub fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            // `</script>` must never appear verbatim if the JSON is inlined into a page.
+            '<' => json.push_str("\\u003c"),
+            c if u32::from(c) < 0x20 => json.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_sections_by_heading_and_text() {
+        let results = search_index().search("wrong order", DEFAULT_RESULT_LIMIT);
+        let section = results
+            .iter()
+            .find(|result| result.slug == "guides/local-manga")
+            .expect("the local manga troubleshooting section");
+        assert_eq!(
+            section.url,
+            "/docs/guides/local-manga#chapters-are-in-the-wrong-order"
+        );
+        assert_eq!(
+            section.heading.as_deref(),
+            Some("Chapters are in the wrong order")
+        );
+        assert!(results.iter().all(|result| result.heading.is_some()));
+
+        let subtitles = search_index().search("subtitles", DEFAULT_RESULT_LIMIT);
+        assert!(!subtitles.is_empty());
+        assert!(search_index().search("zzzz-no-match", 5).is_empty());
+    }
+
+    #[test]
+    fn matches_width_and_kana_variants_of_japanese_text() {
+        let index = SearchIndex::build([(
+            "guides/readings",
+            "---\ntitle: Readings\n---\n\n## Furigana\n\nThe reading goes in ふりがな (furigana) \
+             after the kanji, like `漢字[かんじ]`.\n",
+        )]);
+
+        for query in [
+            "ふりがな",
+            "フリガナ",
+            "ﾌﾘｶﾞﾅ",
+            "Ｆｕｒｉｇａｎａ",
+            "漢字",
+            "かんじ",
+        ] {
+            let results = index.search(query, DEFAULT_RESULT_LIMIT);
+            assert_eq!(
+                results.first().map(|result| result.url.as_str()),
+                Some("/docs/guides/readings#furigana"),
+                "{query}"
+            );
+        }
+        assert!(index.search("ふりかな", DEFAULT_RESULT_LIMIT).is_empty());
+        for query in ["字", "漢", "じ", "ジ"] {
+            assert_eq!(
+                index.search(query, DEFAULT_RESULT_LIMIT).len(),
+                1,
+                "{query}"
+            );
+        }
+        assert!(index.search("書", DEFAULT_RESULT_LIMIT).is_empty());
+    }
+
+    #[test]
+    fn finds_the_kiku_guide_by_its_japanese_text() {
+        for query in ["ふりがな", "Ｆｕｒｉｇａｎａ", "ﾌﾘｶﾞﾅ", "漢字"] {
+            let results = search_index().search(query, DEFAULT_RESULT_LIMIT);
+            assert_eq!(
+                results.first().map(|result| result.slug.as_str()),
+                Some("guides/kiku-guide"),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn ranks_titles_first_and_matches_last_word_as_prefix() {
+        let index = SearchIndex::build([
+            (
+                "guides/novels",
+                "---\ntitle: Novels\ndescription: Reading EPUBs\n---\n\n# Novels\n\nIntro.\n\n## Fonts\n\nPick a novel font.\n",
+            ),
+            (
+                "faq/general",
+                "---\ntitle: General\ndescription: Questions\n---\n\n## Reading\n\nNovels are supported.\n",
+            ),
+        ]);
+
+        let urls: Vec<_> = index
+            .search("nov", 10)
+            .into_iter()
+            .map(|result| result.url)
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "/docs/guides/novels",
+                "/docs/faq/general#reading",
+                "/docs/guides/novels#fonts"
+            ]
+        );
+
+        let json = index.to_json(str::to_string);
+        assert!(json.starts_with(&format!("{{\"ranking\":{{\"titleWeight\":{TITLE_WEIGHT},")));
+        assert!(json.contains(&format!("\"resultsPerPage\":{RESULTS_PER_PAGE},")));
+        assert!(json.contains(
+            "{\"url\":\"/docs/guides/novels\",\"slug\":\"guides/novels\",\"anchor\":null,\
+             \"title\":\"Novels\",\"heading\":null,"
+        ));
+        assert!(json.contains("\"anchor\":\"fonts\",\"title\":\"Novels\",\"heading\":\"Fonts\""));
+        assert!(json.contains(
+            "\"terms\":{\"title\":[\"novels\"],\"heading\":[\"fonts\"],\"description\":[],\
+             \"text\":{\"a\":1,\"font\":1,\"novel\":1,\"pick\":1}}"
+        ));
+    }
+
+    #[test]
+    fn snippets_start_on_character_boundaries_after_folding() {
+        let index = SearchIndex::build([("guides/x", "---\ntitle: X\n---\n\nİ ｶﾞhello İİ\n")]);
+        let results = index.search("hello", 5);
+        assert_eq!(results[0].snippet, "İ ｶﾞhello İİ");
+
+        let source = "First sentence. Ｆｕｒｉｇａｎａ goes here.";
+        let start = first_match(source, &["furigana".to_string()]).expect("a match");
+        assert_eq!(&source[start..], "Ｆｕｒｉｇａｎａ goes here.");
+    }
+}
@@ -0,0 +1,175 @@
+//! Search tokens for mixed English and Japanese text.
+//!
+//! Text is normalised first: full-width ASCII becomes half-width, half-width katakana becomes
+//! full-width, katakana folds to hiragana and everything is lowercased. Runs of Latin letters
+//! and digits are then split into words, while runs of kana and kanji, which have no spaces
+//! between words, are split into overlapping character bigrams.
+
+/// Normalised tokens for `text`: words for Latin script, bigrams for CJK runs.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    for c in normalize(text).chars() {
+        if is_cjk(c) {
+            push_word(&mut tokens, &mut word);
+            cjk_run.push(c);
+        } else if c.is_alphanumeric() {
+            push_bigrams(&mut tokens, &mut cjk_run);
+            word.push(c);
+        } else {
+            push_word(&mut tokens, &mut word);
+            push_bigrams(&mut tokens, &mut cjk_run);
+        }
+    }
+    push_word(&mut tokens, &mut word);
+    push_bigrams(&mut tokens, &mut cjk_run);
+    tokens
+}
+
+/// Folds width and kana variants so "Ｆｕｒｉｇａｎａ" reads as "furigana" and "フリガナ" or
+/// "ﾌﾘｶﾞﾅ" as "ふりがな".
+pub fn normalize(text: &str) -> String {
+    normalize_with_offsets(text).0
+}
+
+/// [`normalize`], plus where each piece of the output came from: `(output byte offset, text
+/// byte offset)` for every source character, in order. Folding changes byte lengths, so
+/// this is the only safe way back from a match in the output to a slice of `text`.
+pub(crate) fn normalize_with_offsets(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut offsets = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((source_offset, c)) = chars.next() {
+        let mut c = fold_width(c);
+        // A voicing mark after a kana merges into it: ｶﾞ and カ\u{3099} both become ガ.
+        if let Some(&(_, mark)) = chars.peek()
+            && let Some(voiced) = apply_voicing_mark(c, mark)
+        {
+            chars.next();
+            c = voiced;
+        }
+        offsets.push((normalized.len(), source_offset));
+        normalized.extend(fold_kana(c).to_lowercase());
+    }
+
+    (normalized, offsets)
+}
+
+/// Whether `term` is a lone kana or kanji. Such terms match inside any bigram, since a
+/// character that ends a run only appears as the second half of one.
+pub(crate) fn is_single_cjk(term: &str) -> bool {
+    let mut chars = term.chars();
+    matches!((chars.next(), chars.next()), (Some(c), None) if is_cjk(c))
+}
+
+fn push_word(tokens: &mut Vec<String>, word: &mut String) {
+    if !word.is_empty() {
+        tokens.push(std::mem::take(word));
+    }
+}
+
+fn push_bigrams(tokens: &mut Vec<String>, run: &mut Vec<char>) {
+    match run.len() {
+        0 => {}
+        1 => tokens.push(run[0].to_string()),
+        _ => tokens.extend(run.windows(2).map(|pair| pair.iter().collect())),
+    }
+    run.clear();
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3041}'..='\u{309F}' // hiragana
+        | '\u{30A0}'..='\u{30FF}' // katakana, including ー
+        | '\u{3400}'..='\u{4DBF}' // CJK extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK compatibility ideographs
+        | '\u{3005}' // 々
+    )
+}
+
+/// Full-width ASCII to ASCII, the ideographic space to a space and half-width katakana to
+/// full-width.
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        '\u{FF61}'..='\u{FF9F}' => HALF_WIDTH_KATAKANA[(c as u32 - 0xFF61) as usize],
+        _ => c,
+    }
+}
+
+/// Full-width forms of U+FF61..=U+FF9F, in order.
+const HALF_WIDTH_KATAKANA: [char; 63] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン', '\u{3099}',
+    '\u{309A}',
+];
+
+const VOICED: &str =
+    "がぎぐげござじずぜぞだぢづでどばびぶべぼガギグゲゴザジズゼゾダヂヅデドバビブベボ";
+const SEMI_VOICED: &str = "ぱぴぷぺぽパピプペポ";
+
+/// Combines a kana with a following dakuten or handakuten, half-width or combining.
+fn apply_voicing_mark(base: char, mark: char) -> Option<char> {
+    let offset = match fold_width(mark) {
+        '\u{3099}' => 1,
+        '\u{309A}' => 2,
+        _ => return None,
+    };
+    if offset == 1 && matches!(base, 'ウ' | 'う') {
+        return Some(if base == 'ウ' { 'ヴ' } else { 'ゔ' });
+    }
+
+    let combined = char::from_u32(base as u32 + offset)?;
+    let accepted = if offset == 1 { VOICED } else { SEMI_VOICED };
+    accepted.contains(combined).then_some(combined)
+}
+
+/// Katakana to the matching hiragana; ー and other characters are unchanged.
+fn fold_kana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_width_and_kana_variants() {
+        assert_eq!(normalize("Ｆｕｒｉｇａｎａ"), "furigana");
+        assert_eq!(normalize("フリガナ"), "ふりがな");
+        assert_eq!(normalize("ﾌﾘｶﾞﾅ"), "ふりがな");
+        assert_eq!(normalize("ハ\u{309A}ン"), "ぱん");
+        assert_eq!(normalize("ｳﾞｫ"), "ゔぉ");
+    }
+
+    #[test]
+    fn splits_latin_words_and_cjk_bigrams() {
+        assert_eq!(
+            tokenize("JMdict の辞書 setup"),
+            ["jmdict", "の辞", "辞書", "setup"]
+        );
+        assert_eq!(tokenize("漢字[かんじ]"), ["漢字", "かん", "んじ"]);
+        assert_eq!(tokenize("ア"), ["あ"]);
+    }
+
+    /// The page template tokenizes queries for static exports with a port of this file.
+    #[test]
+    fn page_template_folds_with_the_same_tables() {
+        let template = crate::layout::PAGE_TEMPLATE;
+        let table: String = HALF_WIDTH_KATAKANA[..61].iter().collect();
+        assert!(template.contains(&format!("'{table}\\u3099\\u309A'")));
+        assert!(template.contains(&format!("const voiced = '{VOICED}';")));
+        assert!(template.contains(&format!("const semiVoiced = '{SEMI_VOICED}';")));
+        assert!(!template.contains("normalize('NFKC')"));
+    }
+}
@@ -0,0 +1,400 @@
+//! The docs as they are on disk now rather than as compiled in. The preview keeps a [`Site`]
+//! and reloads it when files change, so new pages and `nav_*` or `aliases` edits show up
+//! without a rebuild; its `check` and `export` commands read their pages from it too.
+
+use std::{collections::HashMap, fs, io, path::Path, process::Command};
+
+use crate::{
+    NavSection, SourcePage, build_aliases, build_nav_sections, canonical_slug,
+    discover::{discover_pages, slug_for_relative_path},
+    not_found_page, percent_decode, percent_encode_segment,
+    render::docs_path_for_slug,
+    search::SearchIndex,
+    suggest_slugs,
+};
+
+/// What a request for a path should get; see [`Site::route`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocsRoute {
+    /// The path is the canonical URL of this slug: render the page, or the 404 page when no
+    /// page has the slug.
+    Render(String),
+    /// Another spelling of a docs URL, or an alias: redirect permanently to this location,
+    /// which keeps the request's query.
+    Redirect(String),
+    /// Not a docs URL.
+    Outside,
+}
+
+/// Pages, sidebar, aliases and search index read from one docs directory.
+#[derive(Debug, Clone)]
+pub struct Site {
+    /// Every page, the 404 page included, sorted by slug.
+    pages: Vec<SourcePage>,
+    sections: Vec<NavSection>,
+    aliases: HashMap<String, String>,
+    search: SearchIndex,
+}
+
+impl Site {
+    /// Reads every page under `docs_dir`. Files that disappear before they are read, as
+    /// editors saving through a temp file make them do, are left out.
+    ///
+    /// `last_modified` is the file's last commit date in the checkout `docs_dir` is in, as the
+    /// build script records it for the compiled-in pages.
+    pub fn load(docs_dir: &Path) -> io::Result<Site> {
+        let pages = discover_pages(docs_dir)?
+            .into_iter()
+            .filter_map(|page| {
+                let file = page.file(docs_dir);
+                let source = fs::read_to_string(&file).ok()?;
+                let last_modified = git_last_modified(docs_dir, &file);
+                Some(SourcePage {
+                    slug: page.slug,
+                    path: page.path,
+                    source,
+                    last_modified,
+                })
+            })
+            .collect();
+        Ok(Site::new(pages))
+    }
+
+    /// Builds the sidebar, aliases and search index from `pages`, leaving the 404 page out.
+    pub fn new(pages: Vec<SourcePage>) -> Site {
+        let mut site = Site {
+            pages,
+            sections: Vec::new(),
+            aliases: HashMap::new(),
+            search: SearchIndex::default(),
+        };
+        let pairs = || {
+            site.published()
+                .map(|page| (page.slug.as_str(), page.source.as_str()))
+        };
+        let sections = build_nav_sections(pairs());
+        let aliases = build_aliases(pairs());
+        let search = SearchIndex::build(pairs());
+        site.sections = sections;
+        site.aliases = aliases;
+        site.search = search;
+        site
+    }
+
+    /// Every page, the 404 page included.
+    pub fn pages(&self) -> &[SourcePage] {
+        &self.pages
+    }
+
+    /// Every page except the 404 page.
+    pub fn published(&self) -> impl Iterator<Item = &SourcePage> {
+        self.pages
+            .iter()
+            .filter(|page| page.slug != not_found_page().slug)
+    }
+
+    /// The page at exactly `slug`, the 404 page included. Aliases are not followed; see
+    /// [`Site::resolve`].
+    pub fn page(&self, slug: &str) -> Option<&SourcePage> {
+        self.pages.iter().find(|page| page.slug == slug)
+    }
+
+    pub fn sections(&self) -> &[NavSection] {
+        &self.sections
+    }
+
+    pub fn aliases(&self) -> &HashMap<String, String> {
+        &self.aliases
+    }
+
+    pub fn search_index(&self) -> &SearchIndex {
+        &self.search
+    }
+
+    /// The slug of the published page at `slug` or of the page that lists it as an alias.
+    pub fn resolve(&self, slug: &str) -> Option<&str> {
+        match self.published().find(|page| page.slug == slug) {
+            Some(page) => Some(&page.slug),
+            None => self.aliases.get(slug).map(String::as_str),
+        }
+    }
+
+    /// Gives every page one URL. `path` is a request path as sent, still percent-encoded,
+    /// under `/docs` in any case and spelling [`canonical_slug`] accepts, or `/`. Anything
+    /// other than the canonical URL of its page, aliases followed, is redirected there. The
+    /// location is percent-encoded, so it is always a valid `Location` header.
+    pub fn route(&self, path: &str, query: Option<&str>) -> DocsRoute {
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let slug_path = match segments.next() {
+            None => String::new(),
+            Some(first) if first.eq_ignore_ascii_case("docs") => {
+                segments.map(percent_decode).collect::<Vec<_>>().join("/")
+            }
+            Some(_) => return DocsRoute::Outside,
+        };
+
+        let slug = canonical_slug(&slug_path);
+        let slug = self.resolve(&slug).map_or(slug.clone(), str::to_string);
+        let encoded: Vec<String> = slug.split('/').map(percent_encode_segment).collect();
+        let location = docs_path_for_slug(&encoded.join("/"));
+        if path == location {
+            return DocsRoute::Render(slug);
+        }
+        DocsRoute::Redirect(match query {
+            Some(query) => format!("{location}?{query}"),
+            None => location,
+        })
+    }
+
+    /// Published pages that look like what a missing `slug` was meant to be, best match first;
+    /// see [`suggest_slugs`].
+    pub fn suggest(&self, slug: &str) -> Vec<&SourcePage> {
+        let pages = self
+            .published()
+            .map(|page| (page.slug.as_str(), page.source.as_str()));
+        suggest_slugs(slug, pages, &self.aliases)
+            .into_iter()
+            .filter_map(|slug| self.page(slug))
+            .collect()
+    }
+
+    /// Whether the sidebar or the aliases differ from `other`'s, which changes every page.
+    pub fn navigation_differs(&self, other: &Site) -> bool {
+        self.sections != other.sections || self.aliases != other.aliases
+    }
+}
+
+/// Committer date of the last commit touching `file`, in RFC 3339. `None` outside a git
+/// checkout or for files never committed.
+fn git_last_modified(docs_dir: &Path, file: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%cI", "--"])
+        .arg(file.strip_prefix(docs_dir).unwrap_or(file))
+        .current_dir(docs_dir)
+        .output()
+        .ok()?;
+    let date = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !date.is_empty()).then_some(date)
+}
+
+/// The slug of the page `path` holds when it is a Markdown file under `docs_dir`, for mapping
+/// file watcher events to the pages they change.
+pub fn slug_for_changed_file(docs_dir: &Path, path: &Path) -> Option<String> {
+    if is_scratch_file(path) || path.extension()? != "md" {
+        return None;
+    }
+    Some(slug_for_relative_path(path.strip_prefix(docs_dir).ok()?))
+}
+
+/// Hidden and backup files, like the `.page.md.swp` and `page.md~` editors write next to the
+/// file being edited. Nothing is built from them.
+pub fn is_scratch_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.') || name.ends_with('~'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all_pages, discover::DOCS_DIR, nav_sections, page_aliases};
+
+    fn page(slug: &str, source: &str) -> SourcePage {
+        SourcePage {
+            slug: slug.to_string(),
+            path: format!("docs/{slug}.md"),
+            source: source.to_string(),
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn loads_the_same_pages_as_the_build() {
+        let site =
+            Site::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join(DOCS_DIR)).expect("docs dir");
+
+        let published: Vec<SourcePage> = site.published().cloned().collect();
+        let compiled: Vec<SourcePage> = all_pages().iter().map(SourcePage::from).collect();
+        assert_eq!(published, compiled);
+        assert_eq!(
+            site.page("404").map(|page| page.path.as_str()),
+            Some("docs/404.md")
+        );
+        assert_eq!(site.sections(), nav_sections());
+        let aliases: HashMap<String, String> = page_aliases()
+            .iter()
+            .map(|(alias, page)| (alias.clone(), page.slug.to_string()))
+            .collect();
+        assert_eq!(site.aliases(), &aliases);
+    }
+
+    #[test]
+    fn dates_pages_from_the_checkout_they_are_loaded_from() {
+        let dir = std::env::temp_dir().join(format!("site-dates-{}", std::process::id()));
+        fs::create_dir_all(dir.join("guides")).unwrap();
+        fs::write(dir.join("guides/anki.md"), "---\ntitle: Anki\n---\n").unwrap();
+
+        let copy = Site::load(&dir).expect("temp docs dir");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            copy.page("guides/anki")
+                .map(|page| page.last_modified.clone()),
+            Some(None)
+        );
+
+        let checkout =
+            Site::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join(DOCS_DIR)).expect("docs dir");
+        let compiled = all_pages()
+            .iter()
+            .find(|page| page.slug == "guides/local-manga")
+            .expect("compiled page");
+        assert_eq!(
+            checkout
+                .page("guides/local-manga")
+                .and_then(|page| page.last_modified.as_deref()),
+            compiled.last_modified
+        );
+    }
+
+    #[test]
+    fn resolves_published_pages_and_aliases() {
+        let site = Site::new(vec![
+            page("404", "---\ntitle: Not found\naliases: [missing]\n---\n"),
+            page(
+                "guides/anki",
+                "---\ntitle: Anki\nnav_order: 2\naliases: [guides/old-anki]\n---\n",
+            ),
+            page(
+                "guides/local-manga",
+                "---\ntitle: Local manga\nnav_order: 1\n---\n",
+            ),
+        ]);
+
+        assert_eq!(site.resolve("guides/anki"), Some("guides/anki"));
+        assert_eq!(site.resolve("guides/old-anki"), Some("guides/anki"));
+        assert_eq!(site.resolve("404"), None);
+        assert_eq!(site.resolve("missing"), None);
+        assert!(site.page("404").is_some());
+        let items: Vec<&str> = site.sections()[0]
+            .items
+            .iter()
+            .map(|item| item.slug.as_str())
+            .collect();
+        assert_eq!(items, ["guides/local-manga", "guides/anki"]);
+        let found: Vec<String> = site
+            .search_index()
+            .search("anki", 10)
+            .into_iter()
+            .map(|result| result.slug)
+            .collect();
+        assert_eq!(found, ["guides/anki"]);
+        assert!(site.search_index().search("found", 10).is_empty());
+        let suggested: Vec<&str> = site
+            .suggest("guides/old-ankii")
+            .iter()
+            .map(|page| page.slug.as_str())
+            .collect();
+        assert_eq!(suggested, ["guides/anki"]);
+        assert!(site.suggest("not-found").is_empty());
+
+        let mut pages = site.pages().to_vec();
+        pages[2].source.push_str("\nEdited body.\n");
+        assert!(!Site::new(pages.clone()).navigation_differs(&site));
+        pages[2].source = pages[2].source.replace("nav_order: 1", "nav_order: 3");
+        assert!(Site::new(pages).navigation_differs(&site));
+    }
+
+    #[test]
+    fn maps_changed_files_to_slugs() {
+        let docs_dir = Path::new("/work/docs");
+        let slug = |path: &str| slug_for_changed_file(docs_dir, Path::new(path));
+
+        assert_eq!(slug("/work/docs/index.md").as_deref(), Some(""));
+        assert_eq!(
+            slug("/work/docs/guides/index.md").as_deref(),
+            Some("guides")
+        );
+        assert_eq!(
+            slug("/work/docs/guides/local-manga.md").as_deref(),
+            Some("guides/local-manga")
+        );
+        for ignored in [
+            "/work/docs/guides/.local-manga.md.swp",
+            "/work/docs/guides/.hidden.md",
+            "/work/docs/guides/local-manga.md~",
+            "/work/docs/guides/cover.png",
+            "/work/docs/guides",
+            "/work/assets/notes.md",
+        ] {
+            assert_eq!(slug(ignored), None, "{ignored}");
+        }
+        assert!(is_scratch_file(Path::new("/work/assets/.DS_Store")));
+        assert!(!is_scratch_file(Path::new("/work/assets/clip.webm")));
+    }
+
+    #[test]
+    fn routes_every_spelling_to_one_url() {
+        let site = Site::new(vec![
+            page("", "---\ntitle: Docs\n---\n"),
+            page(
+                "guides/anki",
+                "---\ntitle: Anki\naliases: [guides/Old-Anki]\n---\n",
+            ),
+        ]);
+        let render = |slug: &str| DocsRoute::Render(slug.to_string());
+        let redirect = |location: &str| DocsRoute::Redirect(location.to_string());
+
+        assert_eq!(site.route("/docs", None), render(""));
+        assert_eq!(site.route("/docs/guides/anki", None), render("guides/anki"));
+        assert_eq!(
+            site.route("/docs/guides/missing", None),
+            render("guides/missing")
+        );
+        assert_eq!(site.route("/", None), redirect("/docs"));
+        assert_eq!(site.route("/docs/", Some("q=1")), redirect("/docs?q=1"));
+        for path in [
+            "/docs/Guides/Anki",
+            "/DOCS/guides/anki/",
+            "//docs//guides/anki",
+            "/docs/guides/anki.md",
+            "/docs/guides/anki.html",
+            "/docs/guides/anki/index.html",
+            "/docs/guides/anki/INDEX.MD",
+            "/docs/guides/old-anki",
+            "/docs/Guides/OLD-ANKI",
+        ] {
+            assert_eq!(
+                site.route(path, None),
+                redirect("/docs/guides/anki"),
+                "{path}"
+            );
+        }
+        assert_eq!(
+            site.route("/docs/Guides/Anki", Some("from=search&q=Kiku")),
+            redirect("/docs/guides/anki?from=search&q=Kiku")
+        );
+        assert_eq!(
+            site.route("/docs/Guides/Missing", None),
+            redirect("/docs/guides/missing")
+        );
+        assert_eq!(
+            site.route("/docs/guides/anki%2Dguide", None),
+            redirect("/docs/guides/anki-guide")
+        );
+        assert_eq!(
+            site.route("/docs/guides%2Fanki", None),
+            redirect("/docs/guides/anki")
+        );
+        assert_eq!(site.route("/docs/A%0Ab", None), redirect("/docs/a%0Ab"));
+        assert_eq!(site.route("/docs/a%0Ab", None), render("a\nb"));
+        assert_eq!(site.route("/docs/a%3Fb", Some("c")), render("a?b"));
+        assert_eq!(site.route("/docs/a?b", None), redirect("/docs/a%3Fb"));
+        assert_eq!(site.route("/docs/%E3%83%9E", None), render("\u{30de}"));
+        assert_eq!(
+            site.route("/docs/%e3%83%9e", None),
+            redirect("/docs/%E3%83%9E")
+        );
+        assert_eq!(site.route("/assets/clip.webm", None), DocsRoute::Outside);
+        assert_eq!(site.route("/docsearch", None), DocsRoute::Outside);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{DocPage, PAGES, not_found_page, parse_doc, render::escape_html};
+
+/// One `<url>` in `sitemap.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapEntry {
+    pub slug: String,
+    /// `last_reviewed` frontmatter, falling back to the file's last commit date.
+    pub last_modified: Option<String>,
+}
+
+impl SitemapEntry {
+    /// `None` for drafts and the 404 page. `source` is the page's current Markdown, which the
+    /// preview reads from disk.
+    pub fn for_page(page: &DocPage, source: &str) -> Option<Self> {
+        Self::from_source(page.slug, source, page.last_modified)
+    }
+
+    /// Like [`SitemapEntry::for_page`], for pages the preview found on disk after the build.
+    /// `last_modified` is the file's last commit date, if known.
+    pub fn from_source(slug: &str, source: &str, last_modified: Option<&str>) -> Option<Self> {
+        if slug == not_found_page().slug {
+            return None;
+        }
+        let frontmatter = parse_doc(source).frontmatter;
+        if frontmatter.draft {
+            return None;
+        }
+
+        let last_reviewed = frontmatter
+            .last_reviewed
+            .map(|date| date.trim().to_string())
+            .filter(|date| !date.is_empty());
+        Some(SitemapEntry {
+            slug: slug.to_string(),
+            last_modified: last_reviewed.or_else(|| last_modified.map(str::to_string)),
+        })
+    }
+}
+
+/// Sitemap entries for the compiled-in pages.
+pub fn sitemap_entries() -> Vec<SitemapEntry> {
+    PAGES
+        .iter()
+        .filter_map(|page| SitemapEntry::for_page(page, page.source))
+        .collect()
+}
+
+/// Renders `sitemap.xml`. `page_url` maps a slug to the page's absolute URL, since the
+/// preview and the export lay pages out differently.
+pub fn sitemap_xml(entries: &[SitemapEntry], page_url: impl Fn(&str) -> String) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!(
+            "    <loc>{}</loc>\n",
+            escape_html(&page_url(&entry.slug))
+        ));
+        if let Some(last_modified) = &entry.last_modified {
+            xml.push_str(&format!(
+                "    <lastmod>{}</lastmod>\n",
+                escape_html(last_modified)
+            ));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Allows every crawler and points them at the sitemap, when its absolute URL is known.
+pub fn robots_txt(sitemap_url: Option<&str>) -> String {
+    let mut robots = String::from("User-agent: *\nAllow: /\n");
+    if let Some(url) = sitemap_url {
+        robots.push_str(&format!("\nSitemap: {url}\n"));
+    }
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_page;
+
+    #[test]
+    fn lists_published_pages_with_lastmod() {
+        let entries = sitemap_entries();
+        assert!(entries.iter().any(|entry| entry.slug.is_empty()));
+        assert!(entries.iter().all(|entry| entry.slug != "404"));
+
+        let page = find_page("guides/local-manga").expect("local manga guide");
+        let draft = "---\ntitle: Local manga\ndraft: true\n---\n";
+        assert_eq!(SitemapEntry::for_page(page, draft), None);
+
+        let reviewed = "---\ntitle: Local manga\nlast_reviewed: 2025-01-10\n---\n";
+        let entry = SitemapEntry::for_page(page, reviewed).expect("published page");
+        let xml = sitemap_xml(&[entry], |slug| format!("https://docs.example.com/{slug}/"));
+        assert!(xml.contains(
+            "  <url>\n    <loc>https://docs.example.com/guides/local-manga/</loc>\n    <lastmod>2025-01-10</lastmod>\n  </url>\n"
+        ));
+    }
+
+    #[test]
+    fn robots_points_at_the_sitemap() {
+        assert_eq!(
+            robots_txt(Some("https://docs.example.com/sitemap.xml")),
+            "User-agent: *\nAllow: /\n\nSitemap: https://docs.example.com/sitemap.xml\n"
+        );
+    }
+}
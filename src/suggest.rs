@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::{DocPage, PAGES, canonical_slug, page_aliases, parse_doc};
+
+/// Suggestions returned by [`suggest_pages`].
+const MAX_SUGGESTIONS: usize = 5;
+/// Pages scoring below this are too different to be worth suggesting.
+const MIN_SCORE: f64 = 0.6;
+
+/// Pages that look like what a missing `slug` was meant to be, best match first.
+///
+/// The request is compared with each page's slug, last slug segment, title and aliases,
+/// ignoring case and separators: `guides/localmanga` finds `guides/local-manga` and `anki`
+/// finds the Anki setup guide.
+pub fn suggest_pages(slug: &str) -> Vec<&'static DocPage> {
+    let aliases: HashMap<String, String> = page_aliases()
+        .iter()
+        .map(|(alias, page)| (alias.clone(), page.slug.to_string()))
+        .collect();
+    suggest_slugs(
+        slug,
+        PAGES.iter().map(|page| (page.slug, page.source)),
+        &aliases,
+    )
+    .into_iter()
+    .filter_map(|slug| PAGES.iter().find(|page| page.slug == slug))
+    .collect()
+}
+
+/// Like [`suggest_pages`], among `(slug, source)` pairs and their aliases (alias to slug,
+/// as [`crate::build_aliases`] returns them) instead of the compiled-in pages.
+pub fn suggest_slugs<'a>(
+    slug: &str,
+    pages: impl IntoIterator<Item = (&'a str, &'a str)>,
+    aliases: &HashMap<String, String>,
+) -> Vec<&'a str> {
+    let slug = canonical_slug(slug);
+    if slug.is_empty() {
+        return Vec::new();
+    }
+    let queries = keys_for_slug(&slug);
+
+    let mut scored: Vec<(f64, &'a str)> = pages
+        .into_iter()
+        .filter_map(|(page_slug, source)| {
+            let score = page_keys(page_slug, source, aliases)
+                .iter()
+                .flat_map(|key| queries.iter().map(move |query| similarity(query, key)))
+                .fold(0.0, f64::max);
+            (score >= MIN_SCORE).then_some((score, page_slug))
+        })
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| b_score.total_cmp(a_score).then_with(|| a.cmp(b)));
+
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, slug)| slug)
+        .collect()
+}
+
+/// The whole slug and its last segment, compacted.
+fn keys_for_slug(slug: &str) -> Vec<String> {
+    let mut keys = vec![compact(slug)];
+    if let Some((_, last)) = slug.rsplit_once('/') {
+        keys.push(compact(last));
+    }
+    keys.retain(|key| !key.is_empty());
+    keys
+}
+
+fn page_keys(slug: &str, source: &str, aliases: &HashMap<String, String>) -> Vec<String> {
+    let mut keys = keys_for_slug(slug);
+    keys.push(compact(parse_doc(source).title()));
+    for (alias, target) in aliases {
+        if target == slug {
+            keys.extend(keys_for_slug(alias));
+        }
+    }
+    keys
+}
+
+/// Lowercase letters and digits only, so `local-manga`, `Local manga` and `localmanga` agree.
+fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 1.0 for equal keys. A query contained in the key, like a partial name, scores by how much
+/// of the key it covers; anything else by edit distance. A key inside the query does not
+/// count, or every guide would suggest the `guides` index.
+fn similarity(query: &str, key: &str) -> f64 {
+    if query == key {
+        return 1.0;
+    }
+    let query_len = query.chars().count();
+    let key_len = key.chars().count();
+    let longer = query_len.max(key_len);
+    if longer == 0 {
+        return 0.0;
+    }
+
+    if query_len >= 3 && key.contains(query) {
+        return 0.6 + 0.4 * query_len as f64 / key_len as f64;
+    }
+    1.0 - edit_distance(query, key) as f64 / longer as f64
+}
+
+/// Levenshtein distance in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggested(slug: &str) -> Vec<&'static str> {
+        suggest_pages(slug).iter().map(|page| page.slug).collect()
+    }
+
+    #[test]
+    fn suggests_close_pages_first() {
+        assert_eq!(suggested("guides/localmanga")[0], "guides/local-manga");
+        assert_eq!(suggested("anki")[0], "guides/ankiconnect-guide");
+        assert_eq!(suggested("guides/jelyfin")[0], "guides/jellyfin-setup");
+        assert!(suggested("zzzzzzzz").is_empty());
+        assert!(suggested("").is_empty());
+    }
+
+    #[test]
+    fn suggests_among_pages_read_at_runtime() {
+        let pages = [
+            ("guides/new-reader", "---\ntitle: Reader settings\n---\n"),
+            ("guides/sync", "---\ntitle: Sync\n---\n"),
+        ];
+        let aliases =
+            HashMap::from([("guides/old-syncing".to_string(), "guides/sync".to_string())]);
+
+        assert_eq!(
+            suggest_slugs("guides/newreader", pages, &aliases),
+            ["guides/new-reader"]
+        );
+        assert_eq!(
+            suggest_slugs("reader-setings", pages, &aliases),
+            ["guides/new-reader"]
+        );
+        assert_eq!(
+            suggest_slugs("guides/old-synching", pages, &aliases),
+            ["guides/sync"]
+        );
+        assert!(suggest_slugs("guides/local-manga", pages, &aliases).is_empty());
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("マンガ", "マンガ"), 0);
+    }
+}